@@ -11,6 +11,8 @@ pub enum Command {
     publisher: Option<String>,
     #[clap(long)]
     preparer: Option<String>,
+    #[clap(long)]
+    application: Option<String>,
     #[clap(required = true)]
     files: Vec<PathBuf>,
     #[clap(long)]
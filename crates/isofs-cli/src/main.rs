@@ -1,40 +1,46 @@
-use clap::Parser;
-
 mod cli;
 
-fn main() {
-  use isofs::writer::*;
+use clap::Parser;
+
+/// Size of the publisher, data preparer and application identifier fields.
+const IDENTIFIER_LENGTH: usize = 128;
 
-  let mut iso = IsoWriter::new(WriterOptions {
-    sector_size: 2048,
-    standard: Standard::Iso9660,
-  });
+/// Whether `c` is one of the a-characters ECMA-119 allows in these fields.
+fn is_a_character(c: char) -> bool {
+  matches!(c, 'A'..='Z' | '0'..='9' | '_' | ' ' | '!' | '"' | '%'..='?')
+}
 
-  let mut filesystem = isofs::writer::fs::Filesystem::default();
+/// Truncate an identifier to its field length, warning when it doesn't fit.
+fn fit_identifier(field: &str, mut value: String) -> String {
+  if !value.chars().all(is_a_character) {
+    eprintln!("warning: {field} contains characters outside of a-characters");
+  }
 
-  filesystem.upsert_file("a/b/c", "./data/file1.txt").unwrap();
+  if value.len() <= IDENTIFIER_LENGTH {
+    return value;
+  }
 
-  filesystem
-    .upsert_file("a/b/c.txt", "./data/file1.txt")
-    .unwrap();
+  eprintln!("warning: {field} is longer than {IDENTIFIER_LENGTH} bytes and will be truncated");
 
-  dbg!(&filesystem);
+  let mut end = IDENTIFIER_LENGTH;
+  while !value.is_char_boundary(end) {
+    end -= 1;
+  }
 
-  iso.add_volume(isofs::writer::volume::PrimaryVolume {
-    volume_id: "TEST_ISO9660".to_string(),
-    publisher: Some("Publisher".to_string()),
-    preparer: None,
-    filesystem,
-  });
+  value.truncate(end);
+  value
+}
 
-  let file = std::fs::File::create("./data/test-iso9660.iso").unwrap();
-  let mut writer = std::io::BufWriter::new(file);
+/// Report a command that isn't implemented yet and exit unsuccessfully.
+fn unsupported(command: &str) -> ! {
+  eprintln!("error: {command} is not supported yet");
+  std::process::exit(1)
+}
 
-  iso
-    .write(&mut writer)
-    .unwrap();
+fn main() -> Result<(), isofs::writer::error::Error> {
+  use isofs::writer::*;
 
-  /*let cli = cli::Cli::parse();
+  let cli = cli::Cli::parse();
 
   match cli.command {
     cli::Command::Create {
@@ -42,23 +48,55 @@ fn main() {
       volume_id,
       publisher,
       preparer,
+      application,
       files,
       joliet,
       rock_ridge,
     } => {
-      todo!()
-    }
-    cli::Command::Extract { input, output } => {
-      todo!()
-    }
-    cli::Command::List { input, verbose } => {
-      todo!()
-    }
-    cli::Command::Info { input } => {
-      todo!()
-    }
-    cli::Command::Validate { input } => {
-      todo!()
+      if joliet || rock_ridge {
+        eprintln!("warning: Joliet and Rock Ridge are not supported yet and will be ignored");
+      }
+
+      let mut filesystem = fs::Filesystem::default();
+
+      for file in files {
+        // TODO(meowesque): Support adding directories recursively.
+        let Some(name) = file.file_name() else {
+          eprintln!("warning: skipping {}, which has no file name", file.display());
+          continue;
+        };
+
+        filesystem.upsert_file(name, &file)?;
+      }
+
+      let mut volume = volume::PrimaryVolume::builder(volume_id).filesystem(filesystem);
+
+      if let Some(publisher) = publisher {
+        volume = volume.publisher(fit_identifier("publisher", publisher));
+      }
+
+      if let Some(preparer) = preparer {
+        volume = volume.preparer(fit_identifier("preparer", preparer));
+      }
+
+      if let Some(application) = application {
+        volume = volume.application(fit_identifier("application", application));
+      }
+
+      let mut iso = IsoWriter::new(WriterOptions::default());
+
+      iso.add_volume(volume.build());
+
+      let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+      iso.write(&mut writer)?;
+      std::io::Write::flush(&mut writer)?;
     }
-  }*/
+    // TODO(meowesque): Implement these once the reader lands.
+    cli::Command::Extract { .. } => unsupported("extract"),
+    cli::Command::List { .. } => unsupported("list"),
+    cli::Command::Info { .. } => unsupported("info"),
+    cli::Command::Validate { .. } => unsupported("validate"),
+  }
+
+  Ok(())
 }
@@ -0,0 +1,56 @@
+use std::process::Command;
+
+fn run_create(dir: &std::path::Path, args: &[&str]) -> (std::process::Output, Vec<u8>) {
+  let output_path = dir.join("out.iso");
+  let file_path = dir.join("hello.txt");
+  std::fs::write(&file_path, b"hello").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_isofs-cli"))
+    .arg("create")
+    .arg(&output_path)
+    .args(args)
+    .arg(&file_path)
+    .output()
+    .unwrap();
+
+  assert!(output.status.success(), "{output:?}");
+
+  (output, std::fs::read(output_path).unwrap())
+}
+
+#[test]
+fn create_writes_publisher_to_pvd() {
+  let dir = tempfile::tempdir().unwrap();
+  let (_, image) = run_create(dir.path(), &["--publisher", "FOO"]);
+  let pvd = &image[16 * 2048..17 * 2048];
+
+  assert_eq!(&pvd[318..321], b"FOO");
+  assert!(pvd[321..446].iter().all(|&b| b == b' '));
+}
+
+#[test]
+fn create_truncates_long_preparer_with_warning() {
+  let dir = tempfile::tempdir().unwrap();
+  let preparer = "P".repeat(200);
+  let (output, image) = run_create(dir.path(), &["--preparer", &preparer]);
+  let pvd = &image[16 * 2048..17 * 2048];
+
+  assert_eq!(&pvd[446..574], "P".repeat(128).as_bytes());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("truncated"));
+}
+
+#[test]
+fn create_warns_about_lowercase_and_truncates_by_bytes() {
+  let dir = tempfile::tempdir().unwrap();
+  let preparer = "é".repeat(100);
+  let (output, image) = run_create(dir.path(), &["--preparer", &preparer]);
+  let pvd = &image[16 * 2048..17 * 2048];
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(&pvd[446..574], "é".repeat(64).as_bytes());
+  assert!(stderr.contains("truncated"));
+  assert!(stderr.contains("a-characters"));
+
+  let (output, _) = run_create(dir.path(), &["--publisher", "foo"]);
+  assert!(String::from_utf8_lossy(&output.stderr).contains("a-characters"));
+}
@@ -0,0 +1,21 @@
+use std::process::Command;
+
+#[test]
+fn unsupported_commands_fail_without_panicking() {
+  for args in [
+    &["list", "--input", "x"][..],
+    &["extract", "--input", "x"],
+    &["info", "--input", "x"],
+    &["validate", "--input", "x"],
+  ] {
+    let output = Command::new(env!("CARGO_BIN_EXE_isofs-cli"))
+      .args(args)
+      .output()
+      .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert!(stderr.contains("not supported"), "{stderr}");
+    assert!(!stderr.contains("panicked"), "{stderr}");
+  }
+}
@@ -0,0 +1,255 @@
+/// Separator 1, between a file name and its extension.
+pub const SEPARATOR_1: char = '.';
+
+/// Separator 2, between a file identifier and its version number.
+pub const SEPARATOR_2: char = ';';
+
+/// A file identifier split on its separators, `NAME.EXTENSION;VERSION`.
+///
+/// Directory identifiers carry neither separator, so they parse to a bare
+/// `name`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentifierParts<'a> {
+  pub name: &'a str,
+  /// Present whenever separator 1 is, even if the extension is empty.
+  pub extension: Option<&'a str>,
+  pub version: Option<u16>,
+}
+
+impl<'a> IdentifierParts<'a> {
+  pub fn parse(identifier: &'a str) -> Self {
+    let (rest, version) = match identifier.rsplit_once(SEPARATOR_2) {
+      Some((rest, version)) => match version.parse() {
+        Ok(version) => (rest, Some(version)),
+        Err(_) => (identifier, None),
+      },
+      None => (identifier, None),
+    };
+
+    let (name, extension) = match rest.split_once(SEPARATOR_1) {
+      Some((name, extension)) => (name, Some(extension)),
+      None => (rest, None),
+    };
+
+    Self {
+      name,
+      extension,
+      version,
+    }
+  }
+
+  pub fn to_identifier(&self) -> String {
+    let mut identifier = self.name.to_string();
+
+    if let Some(extension) = self.extension {
+      identifier.push(SEPARATOR_1);
+      identifier.push_str(extension);
+    }
+
+    if let Some(version) = self.version {
+      identifier.push(SEPARATOR_2);
+      identifier.push_str(&version.to_string());
+    }
+
+    identifier
+  }
+}
+
+/// Order file identifiers the way ECMA-119 orders directory records: by
+/// name, then extension, each compared as if padded with spaces to equal
+/// length, then by version with the highest first.
+pub fn identifier_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+  fn padded_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    let length = a.len().max(b.len());
+    let padded = |s: &str| {
+      s.bytes()
+        .chain(std::iter::repeat(crate::spec::FILLER))
+        .take(length)
+        .collect::<Vec<_>>()
+    };
+
+    padded(a).cmp(&padded(b))
+  }
+
+  let (a, b) = (IdentifierParts::parse(a), IdentifierParts::parse(b));
+
+  padded_cmp(a.name, b.name)
+    .then_with(|| padded_cmp(a.extension.unwrap_or(""), b.extension.unwrap_or("")))
+    .then_with(|| b.version.cmp(&a.version))
+}
+
+/// ECMA-119 interchange level, bounding the length of file identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterchangeLevel {
+  /// 8.3 file identifiers.
+  Level1,
+  /// File name and extension together up to 30 characters.
+  Level2,
+  /// As level 2, additionally allowing multi-extent files.
+  Level3,
+}
+
+impl InterchangeLevel {
+  /// Maximum `(name, extension)` lengths, and their combined maximum.
+  fn limits(&self) -> (usize, usize, usize) {
+    match self {
+      InterchangeLevel::Level1 => (8, 3, 11),
+      InterchangeLevel::Level2 | InterchangeLevel::Level3 => (30, 30, 30),
+    }
+  }
+
+  /// Longest file name allowed alongside an extension of
+  /// `extension_length`.
+  pub(crate) fn max_file_name_length(&self, extension_length: usize) -> usize {
+    let (max_name, _, max_total) = self.limits();
+    max_name.min(max_total.saturating_sub(extension_length))
+  }
+
+  /// Longest directory identifier allowed.
+  pub(crate) fn max_directory_length(&self) -> usize {
+    match self {
+      InterchangeLevel::Level1 => 8,
+      InterchangeLevel::Level2 | InterchangeLevel::Level3 => 31,
+    }
+  }
+}
+
+/// Map `name` to the file identifier written for it at `level`: uppercased,
+/// restricted to d-characters, truncated, and suffixed with version 1.
+pub fn sanitize_identifier(name: &str, level: InterchangeLevel) -> String {
+  fn d_characters(s: &str) -> String {
+    s.chars()
+      .map(|c| match c.to_ascii_uppercase() {
+        c @ ('A'..='Z' | '0'..='9' | '_') => c,
+        _ => '_',
+      })
+      .collect()
+  }
+
+  let (name, extension) = match name.rsplit_once(SEPARATOR_1) {
+    Some((name, extension)) => (d_characters(name), d_characters(extension)),
+    None => (d_characters(name), String::new()),
+  };
+
+  let (_, max_extension, _) = level.limits();
+  let extension = &extension[..extension.len().min(max_extension)];
+  let name = &name[..name.len().min(level.max_file_name_length(extension.len()))];
+
+  IdentifierParts {
+    name: match (name, extension) {
+      ("", "") => "_",
+      _ => name,
+    },
+    extension: Some(extension),
+    version: Some(1),
+  }
+  .to_identifier()
+}
+
+/// Map `name` to the directory identifier written for it at `level`:
+/// uppercased, restricted to d-characters and truncated, without separators
+/// or a version.
+pub fn sanitize_directory_identifier(name: &str, level: InterchangeLevel) -> String {
+  let max = level.max_directory_length();
+
+  let identifier: String = name
+    .chars()
+    .map(|c| match c.to_ascii_uppercase() {
+      c @ ('A'..='Z' | '0'..='9' | '_') => c,
+      _ => '_',
+    })
+    .take(max)
+    .collect();
+
+  match identifier.as_str() {
+    "" => "_".to_string(),
+    _ => identifier,
+  }
+}
+
+/// Replace the end of `name`, an already sanitized name, with `_n` while
+/// keeping it within `max_length`. Used to tell apart names that sanitize to
+/// the same identifier; `_` keeps the result within the d-characters.
+pub(crate) fn suffix_name(name: &str, n: u32, max_length: usize) -> String {
+  let suffix = format!("_{n}");
+  let keep = name.len().min(max_length.saturating_sub(suffix.len()));
+  format!("{}{suffix}", &name[..keep])
+}
+
+/// The rule a file identifier breaks, from `validate_identifier`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IdentifierViolation {
+  #[error("Identifier is longer than the interchange level allows")]
+  TooLong,
+  #[error("Identifier contains `{0}`, which is not a d-character")]
+  IllegalCharacter(char),
+  #[error("Identifier contains more than one `.`")]
+  TooManyDots,
+  #[error("Identifier has neither a name nor an extension")]
+  EmptyName,
+  #[error("Identifier version {0} is outside of 1..=32767")]
+  InvalidVersion(u16),
+}
+
+/// Check that `name`, optionally suffixed with a `;version`, is a file
+/// identifier valid at `level` as is, without any sanitization.
+pub fn validate_identifier(name: &str, level: InterchangeLevel) -> Result<(), IdentifierViolation> {
+  let parts = IdentifierParts::parse(name);
+  let extension = parts.extension.unwrap_or_default();
+
+  if parts.name.is_empty() && extension.is_empty() {
+    return Err(IdentifierViolation::EmptyName);
+  }
+
+  if extension.contains(SEPARATOR_1) {
+    return Err(IdentifierViolation::TooManyDots);
+  }
+
+  if let Some(version) = parts.version.filter(|v| !(1..=32767).contains(v)) {
+    return Err(IdentifierViolation::InvalidVersion(version));
+  }
+
+  if let Some(c) = parts
+    .name
+    .chars()
+    .chain(extension.chars())
+    .find(|c| !matches!(c, 'A'..='Z' | '0'..='9' | '_'))
+  {
+    return Err(IdentifierViolation::IllegalCharacter(c));
+  }
+
+  let (max_name, max_extension, max_total) = level.limits();
+
+  if parts.name.len() > max_name
+    || extension.len() > max_extension
+    || parts.name.len() + extension.len() > max_total
+  {
+    return Err(IdentifierViolation::TooLong);
+  }
+
+  Ok(())
+}
+
+/// Check that `name` is a directory identifier valid at `level` as is,
+/// without any sanitization.
+pub fn validate_directory_identifier(
+  name: &str,
+  level: InterchangeLevel,
+) -> Result<(), IdentifierViolation> {
+  if name.is_empty() {
+    return Err(IdentifierViolation::EmptyName);
+  }
+
+  if let Some(c) = name
+    .chars()
+    .find(|c| !matches!(c, 'A'..='Z' | '0'..='9' | '_'))
+  {
+    return Err(IdentifierViolation::IllegalCharacter(c));
+  }
+
+  if name.len() > level.max_directory_length() {
+    return Err(IdentifierViolation::TooLong);
+  }
+
+  Ok(())
+}
@@ -2,4 +2,40 @@ pub mod spec;
 pub mod writer;
 pub mod serialize;
 pub mod reader;
-pub mod parse;
\ No newline at end of file
+pub mod parse;
+pub mod identifier;
+
+pub use identifier::identifier_cmp;
+
+/// Write an ISO 9660 image to `output` holding a single primary volume with
+/// `files`, given as `(destination, source)` path pairs.
+pub fn create<O, D, S>(
+  output: O,
+  volume_id: &str,
+  files: &[(D, S)],
+) -> Result<(), writer::error::Error>
+where
+  O: AsRef<std::path::Path>,
+  D: AsRef<std::path::Path>,
+  S: AsRef<std::path::Path>,
+{
+  let mut filesystem = writer::fs::Filesystem::default();
+
+  for (destination, source) in files {
+    filesystem.upsert_file(destination, source)?;
+  }
+
+  let mut iso = writer::IsoWriter::new(writer::WriterOptions::default());
+
+  iso.add_volume(
+    writer::volume::PrimaryVolume::builder(volume_id)
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  let mut output = std::io::BufWriter::new(std::fs::File::create(output)?);
+  iso.write(&mut output)?;
+  std::io::Write::flush(&mut output)?;
+
+  Ok(())
+}
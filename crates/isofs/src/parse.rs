@@ -9,24 +9,310 @@ pub enum IsoParseError {
     got: usize,
     when_parsing: &'static str,
   },
+  #[error("Expected volume descriptor type {expected}, but got {got} when parsing {when_parsing}")]
+  UnexpectedDescriptorType {
+    expected: u8,
+    got: u8,
+    when_parsing: &'static str,
+  },
+  #[error("Invalid digits {digits:?} when parsing {when_parsing}")]
+  InvalidDigits {
+    digits: Vec<u8>,
+    when_parsing: &'static str,
+  },
+  #[error("Declared record length {declared} is shorter than the {minimum} bytes its fields occupy when parsing {when_parsing}")]
+  RecordLengthMismatch {
+    declared: u8,
+    minimum: usize,
+    when_parsing: &'static str,
+  },
+  #[error("Identifier of {length} bytes does not fit in {capacity} bytes when parsing {when_parsing}")]
+  IdentifierTooLong {
+    length: u8,
+    capacity: usize,
+    when_parsing: &'static str,
+  },
 }
 
 pub trait IsoParse: Sized {
   fn parse(input: &[u8]) -> Result<Self, IsoParseError>;
 }
 
-impl IsoParse for spec::PrimaryVolumeDescriptor {
+fn expect_len(
+  inp: &[u8],
+  expected_atleast: usize,
+  when_parsing: &'static str,
+) -> Result<(), IsoParseError> {
+  if inp.len() < expected_atleast {
+    return Err(InputTooSmall {
+      expected_atleast,
+      got: inp.len(),
+      when_parsing,
+    });
+  }
+
+  Ok(())
+}
+
+/// Callers must have checked that `inp` holds at least `N` bytes.
+fn array<const N: usize>(inp: &[u8]) -> [u8; N] {
+  inp[..N].try_into().unwrap()
+}
+
+fn u16_lsb(inp: &[u8]) -> u16 {
+  u16::from_le_bytes(array(inp))
+}
+
+fn u32_lsb(inp: &[u8]) -> u32 {
+  u32::from_le_bytes(array(inp))
+}
+
+fn u32_msb(inp: &[u8]) -> u32 {
+  u32::from_be_bytes(array(inp))
+}
+
+fn digits(inp: &[u8], when_parsing: &'static str) -> Result<u16, IsoParseError> {
+  if !inp.iter().all(u8::is_ascii_digit) {
+    return Err(InvalidDigits {
+      digits: inp.to_vec(),
+      when_parsing,
+    });
+  }
+
+  Ok(inp.iter().fold(0, |acc, d| acc * 10 + (d - b'0') as u16))
+}
+
+fn expect_descriptor_type(
+  inp: &[u8],
+  expected: spec::VolumeDescriptorType,
+  when_parsing: &'static str,
+) -> Result<(), IsoParseError> {
+  let expected: u8 = expected.into();
+
+  if inp[0] != expected {
+    return Err(UnexpectedDescriptorType {
+      expected,
+      got: inp[0],
+      when_parsing,
+    });
+  }
+
+  Ok(())
+}
+
+impl IsoParse for spec::DigitsDate {
   fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
-    if inp.len() < 2048 {
-      return Err(InputTooSmall {
-        expected_atleast: 2048,
-        got: inp.len(),
-        when_parsing: "PrimaryVolumeDescriptor",
+    const WHEN: &str = "DigitsDate";
+    expect_len(inp, 17, WHEN)?;
+
+    // Some authoring tools leave unset dates as all zero bytes rather than
+    // ASCII zeros.
+    if inp.iter().all(|&b| b == 0) {
+      return Ok(Self::unspecified());
+    }
+
+    Ok(Self {
+      year: spec::DigitsYear(digits(&inp[0..4], WHEN)?),
+      month: spec::DigitsMonth(digits(&inp[4..6], WHEN)? as u8),
+      day: spec::DigitsDay(digits(&inp[6..8], WHEN)? as u8),
+      hour: spec::DigitsHour(digits(&inp[8..10], WHEN)? as u8),
+      minute: spec::DigitsMinute(digits(&inp[10..12], WHEN)? as u8),
+      second: spec::DigitsSecond(digits(&inp[12..14], WHEN)? as u8),
+      hundreths: spec::DigitsHundreths(digits(&inp[14..16], WHEN)? as u8),
+      gmt_offset: spec::NumericalGmtOffset(inp[16] as i8),
+    })
+  }
+}
+
+impl IsoParse for spec::NumericalDate {
+  fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
+    expect_len(inp, 7, "NumericalDate")?;
+
+    Ok(Self {
+      years_since_1900: spec::NumericalYear(inp[0]),
+      month: spec::NumericalMonth(inp[1]),
+      day: spec::NumericalDay(inp[2]),
+      hour: spec::NumericalHour(inp[3]),
+      minute: spec::NumericalMinute(inp[4]),
+      second: spec::NumericalSecond(inp[5]),
+      gmt_offset: spec::NumericalGmtOffset(inp[6] as i8),
+    })
+  }
+}
+
+impl IsoParse for spec::RootDirectoryRecord {
+  fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
+    expect_len(inp, 34, "RootDirectoryRecord")?;
+
+    Ok(Self {
+      extent_location: u32_lsb(&inp[2..6]),
+      data_length: u32_lsb(&inp[10..14]),
+      recording_date: spec::NumericalDate::parse(&inp[18..25])?,
+      file_flags: spec::FileFlags::from_bits_retain(inp[25]),
+      file_unit_size: inp[26],
+      interleave_gap_size: inp[27],
+      volume_sequence_number: u16_lsb(&inp[28..30]),
+    })
+  }
+}
+
+impl IsoParse for spec::DirectoryRecord<spec::NoExtension> {
+  fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
+    const WHEN: &str = "DirectoryRecord";
+    expect_len(inp, 33, WHEN)?;
+
+    let file_identifier_length = inp[32];
+    expect_len(inp, 33 + file_identifier_length as usize, WHEN)?;
+
+    // A padding byte follows identifiers of even length.
+    let minimum =
+      33 + file_identifier_length as usize + file_identifier_length.is_multiple_of(2) as usize;
+
+    if (inp[0] as usize) < minimum {
+      return Err(RecordLengthMismatch {
+        declared: inp[0],
+        minimum,
+        when_parsing: WHEN,
       });
-    }    
+    }
+
+    // Truncating would change the identifier's length, and with it the
+    // record's, on re-serialization.
+    let capacity = <spec::NoExtension as spec::Extension>::FileIdentifier::CAPACITY;
+
+    if file_identifier_length as usize > capacity {
+      return Err(IdentifierTooLong {
+        length: file_identifier_length,
+        capacity,
+        when_parsing: WHEN,
+      });
+    }
+
+    // Whatever follows the identifier and its padding is System Use.
+    expect_len(inp, inp[0] as usize, WHEN)?;
+
+    Ok(Self {
+      length: inp[0],
+      extended_attribute_length: inp[1],
+      extent_location: u32_lsb(&inp[2..6]),
+      data_length: u32_lsb(&inp[10..14]),
+      recording_date: spec::NumericalDate::parse(&inp[18..25])?,
+      file_flags: spec::FileFlags::from_bits_retain(inp[25]),
+      file_unit_size: inp[26],
+      interleave_gap_size: inp[27],
+      volume_sequence_number: u16_lsb(&inp[28..30]),
+      file_identifier_length,
+      file_identifier: spec::FileIdentifier::from_bytes_truncated(
+        &inp[33..33 + file_identifier_length as usize],
+      ),
+      system_use: inp[minimum..inp[0] as usize].to_vec(),
+    })
+  }
+}
+
+impl IsoParse for spec::PrimaryVolumeDescriptor {
+  fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
+    const WHEN: &str = "PrimaryVolumeDescriptor";
+    expect_len(inp, 2048, WHEN)?;
+    expect_descriptor_type(inp, spec::VolumeDescriptorType::Primary, WHEN)?;
+
+    Ok(Self {
+      standard_identifier: spec::StandardIdentifier::from_bytes(&array(&inp[1..6])),
+      version: inp[6].into(),
+      system_identifier: spec::ACharacters(array(&inp[8..40])),
+      volume_identifier: spec::DCharacters(array(&inp[40..72])),
+      volume_space_size: u32_lsb(&inp[80..84]),
+      volume_set_size: u16_lsb(&inp[120..122]),
+      volume_sequence_number: u16_lsb(&inp[124..126]),
+      logical_block_size: u16_lsb(&inp[128..130]),
+      path_table_size: u32_lsb(&inp[132..136]),
+      type_l_path_table_location: u32_lsb(&inp[140..144]),
+      optional_type_l_path_table_location: u32_lsb(&inp[144..148]),
+      type_m_path_table_location: u32_msb(&inp[148..152]),
+      optional_type_m_path_table_location: u32_msb(&inp[152..156]),
+      root_directory_record: spec::RootDirectoryRecord::parse(&inp[156..190])?,
+      volume_set_identifier: spec::DCharacters(array(&inp[190..318])),
+      publisher_identifier: spec::ACharacters(array(&inp[318..446])),
+      data_preparer_identifier: spec::ACharacters(array(&inp[446..574])),
+      application_identifier: spec::ACharacters(array(&inp[574..702])),
+      copyright_file_identifier: spec::DCharacters(array(&inp[702..739])),
+      abstract_file_identifier: spec::DCharacters(array(&inp[739..776])),
+      bibliographic_file_identifier: spec::DCharacters(array(&inp[776..813])),
+      creation_date: spec::DigitsDate::parse(&inp[813..830])?,
+      modification_date: spec::DigitsDate::parse(&inp[830..847])?,
+      expiration_date: spec::DigitsDate::parse(&inp[847..864])?,
+      effective_date: spec::DigitsDate::parse(&inp[864..881])?,
+      file_structure_version: inp[881].into(),
+      application_use: array(&inp[883..1395]),
+    })
+  }
+}
+
+impl IsoParse for spec::SupplementaryVolumeDescriptor {
+  fn parse(inp: &[u8]) -> Result<Self, IsoParseError> {
+    const WHEN: &str = "SupplementaryVolumeDescriptor";
+    expect_len(inp, 2048, WHEN)?;
+    expect_descriptor_type(inp, spec::VolumeDescriptorType::Supplementary, WHEN)?;
+
+    Ok(Self {
+      standard_identifier: spec::StandardIdentifier::from_bytes(&array(&inp[1..6])),
+      version: inp[6].into(),
+      volume_flags: spec::VolumeFlags::from_bits_retain(inp[7]),
+      system_identifier: spec::A1Characters(array(&inp[8..40])),
+      volume_identifier: spec::D1Characters(array(&inp[40..72])),
+      volume_space_size: u32_lsb(&inp[80..84]),
+      escape_sequences: spec::EscapeSequences(array(&inp[88..120])),
+      volume_set_size: u16_lsb(&inp[120..122]),
+      volume_sequence_number: u16_lsb(&inp[124..126]),
+      logical_block_size: u16_lsb(&inp[128..130]),
+      path_table_size: u32_lsb(&inp[132..136]),
+      type_l_path_table_location: u32_lsb(&inp[140..144]),
+      optional_type_l_path_table_location: u32_lsb(&inp[144..148]),
+      type_m_path_table_location: u32_msb(&inp[148..152]),
+      optional_type_m_path_table_location: u32_msb(&inp[152..156]),
+      root_directory_record: spec::RootDirectoryRecord::parse(&inp[156..190])?,
+      volume_set_identifier: spec::D1Characters(array(&inp[190..318])),
+      publisher_identifier: spec::A1Characters(array(&inp[318..446])),
+      data_preparer_identifier: spec::A1Characters(array(&inp[446..574])),
+      application_identifier: spec::A1Characters(array(&inp[574..702])),
+      copyright_file_identifier: spec::D1Characters(array(&inp[702..739])),
+      abstract_file_identifier: spec::D1Characters(array(&inp[739..776])),
+      bibliographic_file_identifier: spec::D1Characters(array(&inp[776..813])),
+      creation_date: spec::DigitsDate::parse(&inp[813..830])?,
+      modification_date: spec::DigitsDate::parse(&inp[830..847])?,
+      expiration_date: spec::DigitsDate::parse(&inp[847..864])?,
+      effective_date: spec::DigitsDate::parse(&inp[864..881])?,
+      file_structure_version: inp[881].into(),
+      application_use: array(&inp[883..1395]),
+    })
+  }
+}
 
-    
+impl spec::VolumeDescriptor {
+  /// Parse the volume descriptor in `sector` without panicking on any input.
+  ///
+  /// Yields `None` for sectors that aren't `CD001` descriptors, or whose type
+  /// isn't parsed by this crate, and an error for recognized descriptors
+  /// whose fields are malformed.
+  pub fn try_parse_sector(sector: &[u8; 2048]) -> Result<Option<Self>, IsoParseError> {
+    if !matches!(
+      spec::StandardIdentifier::from_bytes(&array(&sector[1..6])),
+      spec::StandardIdentifier::Cd001
+    ) {
+      return Ok(None);
+    }
 
-    todo!()
+    Ok(Some(match spec::VolumeDescriptorType::from(sector[0]) {
+      spec::VolumeDescriptorType::Primary => {
+        Self::Primary(spec::PrimaryVolumeDescriptor::parse(sector)?)
+      }
+      spec::VolumeDescriptorType::Supplementary => {
+        Self::Supplementary(spec::SupplementaryVolumeDescriptor::parse(sector)?)
+      }
+      spec::VolumeDescriptorType::Terminator => {
+        Self::Terminator(spec::VolumeDescriptorSetTerminator)
+      }
+      _ => return Ok(None),
+    }))
   }
 }
@@ -6,11 +6,27 @@ type Result<T> = std::result::Result<T, IsoSerializeError>;
 pub enum IsoSerializeError {
   #[error("Output buffer too small")]
   OutputBufferTooSmall { expected: usize, actual: usize },
+  #[error("Value of {length} bytes does not fit field {field} of {capacity} bytes")]
+  FieldLengthMismatch {
+    field: &'static str,
+    length: usize,
+    capacity: usize,
+  },
+  #[error("Field {field} value {value} is outside of {min}..={max}")]
+  FieldOutOfRange {
+    field: &'static str,
+    value: u16,
+    min: u16,
+    max: u16,
+  },
 }
 
 pub trait IsoSerialize {
   fn extent(&self) -> usize;
 
+  /// # Safety
+  ///
+  /// `out` must be at least `self.extent()` bytes long.
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()>;
 
   fn serialize(&self, out: &mut [u8]) -> Result<usize> {
@@ -160,7 +176,13 @@ impl IsoSerialize for VolumeFlags {
 
 impl<const LENGTH: usize> IsoSerialize for FileIdentifier<LENGTH> {
   fn extent(&self) -> usize {
-    self.0.iter().position(|&b| b == 0).unwrap_or(self.0.len())
+    // A leading zero byte is the `.` identifier rather than an empty one.
+    self
+      .0
+      .iter()
+      .position(|&b| b == 0)
+      .unwrap_or(self.0.len())
+      .max(1)
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
@@ -257,16 +279,35 @@ impl IsoSerialize for FileStructureVersion {
   }
 }
 
+/// Write `value` as zero-padded ASCII digits filling `out`, rejecting values
+/// above `max` rather than overrunning the field.
+fn serialize_digits(value: u16, max: u16, field: &'static str, out: &mut [u8]) -> Result<()> {
+  if value > max {
+    return Err(IsoSerializeError::FieldOutOfRange {
+      field,
+      value,
+      min: 0,
+      max,
+    });
+  }
+
+  let mut rest = value;
+
+  for digit in out.iter_mut().rev() {
+    *digit = b'0' + (rest % 10) as u8;
+    rest /= 10;
+  }
+
+  Ok(())
+}
+
 impl IsoSerialize for DigitsYear {
   fn extent(&self) -> usize {
     4
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:04}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0, 9999, "DigitsYear", &mut out[..4])
   }
 }
 
@@ -276,10 +317,7 @@ impl IsoSerialize for DigitsMonth {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 12, "DigitsMonth", &mut out[..2])
   }
 }
 
@@ -289,10 +327,7 @@ impl IsoSerialize for DigitsDay {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 31, "DigitsDay", &mut out[..2])
   }
 }
 
@@ -302,10 +337,7 @@ impl IsoSerialize for DigitsHour {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 23, "DigitsHour", &mut out[..2])
   }
 }
 
@@ -315,10 +347,7 @@ impl IsoSerialize for DigitsMinute {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 59, "DigitsMinute", &mut out[..2])
   }
 }
 
@@ -328,10 +357,7 @@ impl IsoSerialize for DigitsSecond {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    // TODO(meowesque): This is inefficient
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 59, "DigitsSecond", &mut out[..2])
   }
 }
 
@@ -341,9 +367,7 @@ impl IsoSerialize for DigitsHundreths {
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    let s = format!("{:02}", self.0);
-    out[..s.len()].copy_from_slice(s.as_bytes());
-    Ok(())
+    serialize_digits(self.0.into(), 99, "DigitsHundreths", &mut out[..2])
   }
 }
 
@@ -529,6 +553,29 @@ impl IsoSerialize for NumericalDate {
   }
 }
 
+impl PrimaryVolumeDescriptor {
+  /// Overwrite a single field of a serialized descriptor, leaving every other
+  /// byte untouched. Character fields may be given shorter values, which are
+  /// padded with filler; all other fields must be given in full.
+  pub fn patch_field(out: &mut [u8; 2048], field: PvdField, value: &[u8]) -> Result<()> {
+    let range = field.range();
+
+    if value.len() > range.len() || (value.len() < range.len() && !field.is_character_field()) {
+      return Err(IsoSerializeError::FieldLengthMismatch {
+        field: field.name(),
+        length: value.len(),
+        capacity: range.len(),
+      });
+    }
+
+    let out = &mut out[range];
+    out[..value.len()].copy_from_slice(value);
+    out[value.len()..].fill(FILLER);
+
+    Ok(())
+  }
+}
+
 impl IsoSerialize for PrimaryVolumeDescriptor {
   fn extent(&self) -> usize {
     2048
@@ -538,19 +585,19 @@ impl IsoSerialize for PrimaryVolumeDescriptor {
     out[0] = VolumeDescriptorType::Primary.into();
     out[1..6].copy_from_slice(self.standard_identifier.as_bytes());
     out[6] = self.version.into();
-    out[7] = 0;
+    out[7] = RESERVED;
     self
       .system_identifier
       .serialize_unchecked(&mut out[8..40])?;
     self
       .volume_identifier
       .serialize_unchecked(&mut out[40..72])?;
-    out[72..80].fill(0);
+    out[72..80].fill(RESERVED);
 
     out[80..84].copy_from_slice(&self.volume_space_size.to_le_bytes());
     out[84..88].copy_from_slice(&self.volume_space_size.to_be_bytes());
 
-    out[88..120].fill(0);
+    out[88..120].fill(RESERVED);
 
     out[120..122].copy_from_slice(&self.volume_set_size.to_le_bytes());
     out[122..124].copy_from_slice(&self.volume_set_size.to_be_bytes());
@@ -635,9 +682,9 @@ impl IsoSerialize for PrimaryVolumeDescriptor {
       .serialize_unchecked(&mut out[864..881])?;
 
     out[881] = self.file_structure_version.into();
-    out[882] = 0;
+    out[882] = RESERVED;
     out[883..1395].copy_from_slice(&self.application_use);
-    out[1395..2048].fill(0);
+    out[1395..2048].fill(RESERVED);
 
     Ok(())
   }
@@ -659,10 +706,10 @@ impl IsoSerialize for SupplementaryVolumeDescriptor {
     self
       .volume_identifier
       .serialize_unchecked(&mut out[40..72])?;
-    out[72..80].fill(0);
+    out[72..80].fill(RESERVED);
     out[80..84].copy_from_slice(&self.volume_space_size.to_le_bytes());
     out[84..88].copy_from_slice(&self.volume_space_size.to_be_bytes());
-    out[88..120].fill(0); // TODO(meowesque): Add escape sequences
+    out[88..120].copy_from_slice(&self.escape_sequences.0);
     out[120..122].copy_from_slice(&self.volume_set_size.to_le_bytes());
     out[122..124].copy_from_slice(&self.volume_set_size.to_be_bytes());
     out[124..126].copy_from_slice(&self.volume_sequence_number.to_le_bytes());
@@ -673,8 +720,8 @@ impl IsoSerialize for SupplementaryVolumeDescriptor {
     out[136..140].copy_from_slice(&self.path_table_size.to_be_bytes());
     out[140..144].copy_from_slice(&self.type_l_path_table_location.to_le_bytes());
     out[144..148].copy_from_slice(&self.optional_type_l_path_table_location.to_le_bytes());
-    out[148..152].copy_from_slice(&self.type_m_path_table_location.to_le_bytes()); // TODO(meowesque): Check if the endianness is correct
-    out[152..156].copy_from_slice(&self.optional_type_m_path_table_location.to_le_bytes());
+    out[148..152].copy_from_slice(&self.type_m_path_table_location.to_be_bytes());
+    out[152..156].copy_from_slice(&self.optional_type_m_path_table_location.to_be_bytes());
     self
       .root_directory_record
       .serialize_unchecked(&mut out[156..190])?;
@@ -710,9 +757,9 @@ impl IsoSerialize for SupplementaryVolumeDescriptor {
       .effective_date
       .serialize_unchecked(&mut out[864..881])?;
     out[881] = self.file_structure_version.into();
-    out[882] = 0;
+    out[882] = RESERVED;
     out[883..1395].copy_from_slice(&self.application_use);
-    out[1395..2048].fill(0);
+    out[1395..2048].fill(RESERVED);
 
     Ok(())
   }
@@ -727,7 +774,7 @@ impl IsoSerialize for VolumePartitionDescriptor {
     out[0] = VolumeDescriptorType::Partition.into();
     out[1..6].copy_from_slice(self.standard_identifier.as_bytes());
     out[6] = self.version.into();
-    out[7] = 0;
+    out[7] = RESERVED;
     self
       .system_identifier
       .serialize_unchecked(&mut out[8..40])?;
@@ -738,7 +785,7 @@ impl IsoSerialize for VolumePartitionDescriptor {
     out[76..80].copy_from_slice(&self.volume_partition_location.to_be_bytes());
     out[80..84].copy_from_slice(&self.volume_partition_size.to_le_bytes());
     out[84..88].copy_from_slice(&self.volume_partition_size.to_be_bytes());
-    out[88..2048].fill(0);
+    out[88..2048].fill(RESERVED);
 
     Ok(())
   }
@@ -753,22 +800,28 @@ impl IsoSerialize for VolumeDescriptorSetTerminator {
     out[0] = VolumeDescriptorType::Terminator.into();
     out[1..6].copy_from_slice(b"CD001"); // TODO(meowesque): This might be different depending on the serialization context
     out[6] = VolumeDescriptorVersion::Standard.into();
-    out[7..2048].fill(0);
+    out[7..2048].fill(RESERVED);
 
     Ok(())
   }
 }
 
-impl<Ext: Extension> IsoSerialize for DirectoryRecord<Ext>
-where
-  Ext::FileIdentifier: IsoSerialize,
-{
+impl<Ext: Extension> IsoSerialize for DirectoryRecord<Ext> {
   fn extent(&self) -> usize {
-    33 + self.file_identifier.extent() + (self.file_identifier.extent() % 2)
+    // A padding byte follows identifiers of even length.
+    33 + self.file_identifier.extent()
+      + self.file_identifier.extent().is_multiple_of(2) as usize
+      + self.system_use.len()
   }
 
   unsafe fn serialize_unchecked(&self, out: &mut [u8]) -> Result<()> {
-    out[0] = self.length as u8;
+    debug_assert_eq!(
+      self.length as usize,
+      self.extent(),
+      "DirectoryRecord length disagrees with its serialized size"
+    );
+
+    out[0] = self.length;
     out[1] = self.extended_attribute_length;
     out[2..6].copy_from_slice(&self.extent_location.to_le_bytes());
     out[6..10].copy_from_slice(&self.extent_location.to_be_bytes());
@@ -781,14 +834,14 @@ where
     out[28..30].copy_from_slice(&self.volume_sequence_number.to_le_bytes());
     out[30..32].copy_from_slice(&self.volume_sequence_number.to_be_bytes());
     out[32] = self.file_identifier.extent() as u8;
-    // TODO(meowesque): Check if this is right ?
     self
       .file_identifier
       .serialize_unchecked(&mut out[33..33 + self.file_identifier.extent()])?;
-    // TODO(meowesque): Check if this is right ?
-    if self.file_identifier.extent() % 2 == 1 {
+    if self.file_identifier.extent().is_multiple_of(2) {
       out[33 + self.file_identifier.extent()] = 0;
     }
+    let system_use_start = self.extent() - self.system_use.len();
+    out[system_use_start..self.extent()].copy_from_slice(&self.system_use);
 
     Ok(())
   }
@@ -815,7 +868,8 @@ impl IsoSerialize for RootDirectoryRecord {
     out[27] = self.interleave_gap_size;
     out[28..30].copy_from_slice(&self.volume_sequence_number.to_le_bytes());
     out[30..32].copy_from_slice(&self.volume_sequence_number.to_be_bytes());
-    out[32] = 0; // NOTE(meowesque): This might be one
+    // The root's identifier is the single `0x00` byte.
+    out[32] = 1;
     out[33] = 0;
 
     Ok(())
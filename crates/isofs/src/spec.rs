@@ -1,8 +1,16 @@
 //! UDF and ISO 9660 specification types including extensions such as Joliet and Rock Ridge.
 
+/// Identifier encoding of an ISO 9660 extension, parameterizing
+/// `DirectoryRecord` and `PathTableRecord`.
+///
+/// Implement it downstream to record identifiers in a custom encoding. An
+/// identifier's `extent()` is its recorded length, and it serializes to
+/// exactly the bytes that follow the length field. Implementing
+/// `writer::WriterExtension` as well lets `writer::IsoWriter` write the
+/// directory tree with it.
 pub trait Extension {
-  type FileIdentifier: std::fmt::Debug;
-  type DirectoryIdentifier: std::fmt::Debug;
+  type FileIdentifier: std::fmt::Debug + crate::serialize::IsoSerialize;
+  type DirectoryIdentifier: std::fmt::Debug + crate::serialize::IsoSerialize;
 }
 
 /// No extensions; Standard ISO 9660 only.
@@ -10,7 +18,9 @@ pub trait Extension {
 pub struct NoExtension;
 
 impl Extension for NoExtension {
-  type FileIdentifier = FileIdentifier<32>;
+  /// Room for a level 2 identifier: 30 characters, both separators and a
+  /// five digit version.
+  type FileIdentifier = FileIdentifier<37>;
   type DirectoryIdentifier = DirectoryIdentifier<31>;
 }
 
@@ -35,40 +45,81 @@ impl Extension for JolietExtension {
   type DirectoryIdentifier = JolietDirectoryIdentifier;
 }
 
+/// Fills the unused tail of character fields.
+pub const FILLER: u8 = b' ';
+
+/// Content of reserved fields and unused descriptor areas.
+pub const RESERVED: u8 = 0x00;
+
 /// `[\s\!\"\%\&\'\(\)\*\+\,\-\.\/0-9A-Z\:\;\<\=\>\?\_A-Z0-9]`
-#[derive(Debug)]
 pub struct ACharacters<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
 impl<const LENGTH: usize> ACharacters<LENGTH> {
-  /// Convert from a byte slice, truncating or zero-padding as necessary.
+  /// Convert from a byte slice, truncating or space-padding as necessary.
   pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
     // TODO(meowesque): Validate characters?
-    let mut cs = [0u8; LENGTH];
+    let mut cs = [FILLER; LENGTH];
     cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
     Self(cs)
   }
+
+  /// The identifier without its trailing padding.
+  pub fn as_trimmed_str(&self) -> std::borrow::Cow<'_, str> {
+    trim_padding(&self.0)
+  }
+}
+
+/// Strip the trailing spaces (or, from older writers, NULs) that pad an
+/// identifier field.
+fn trim_padding(bytes: &[u8]) -> std::borrow::Cow<'_, str> {
+  let end = bytes
+    .iter()
+    .rposition(|&b| b != b' ' && b != 0)
+    .map_or(0, |ix| ix + 1);
+
+  String::from_utf8_lossy(&bytes[..end])
 }
 
 /// `[0-9A-Z_]``
-#[derive(Debug)]
 pub struct DCharacters<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
 impl<const LENGTH: usize> DCharacters<LENGTH> {
-  /// Convert from a byte slice, truncating or zero-padding as necessary.
+  /// Convert from a byte slice, truncating or space-padding as necessary.
   pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
     // TODO(meowesque): Validate characters?
-    let mut cs = [0u8; LENGTH];
+    let mut cs = [FILLER; LENGTH];
     cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
     Self(cs)
   }
+
+  /// The identifier without its trailing padding.
+  pub fn as_trimmed_str(&self) -> std::borrow::Cow<'_, str> {
+    trim_padding(&self.0)
+  }
 }
 
-#[derive(Debug)]
 pub struct A1Characters<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
-#[derive(Debug)]
+impl<const LENGTH: usize> A1Characters<LENGTH> {
+  /// Convert from a byte slice, truncating or space-padding as necessary.
+  pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
+    let mut cs = [FILLER; LENGTH];
+    cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
+    Self(cs)
+  }
+}
+
 pub struct D1Characters<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
+impl<const LENGTH: usize> D1Characters<LENGTH> {
+  /// Convert from a byte slice, truncating or space-padding as necessary.
+  pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
+    let mut cs = [FILLER; LENGTH];
+    cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
+    Self(cs)
+  }
+}
+
 /// Escape sequences conforming to ISO/IEC 2022, including the escape characters.
 ///
 /// If all the bytes of the escape sequences are zero, it shall mean that the set
@@ -76,14 +127,21 @@ pub struct D1Characters<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 #[derive(Debug)]
 pub struct EscapeSequences<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
+impl<const LENGTH: usize> EscapeSequences<LENGTH> {
+  /// Convert from a byte slice, truncating or zero-padding as necessary.
+  pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
+    let mut cs = [0u8; LENGTH];
+    cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
+    Self(cs)
+  }
+}
+
 /// Escape sequences conforming to ISO/IEC 2022, excluding the escape characters.
 #[derive(Debug)]
 pub struct VariadicEscapeSequences(pub(crate) Vec<u8>);
 
-#[derive(Debug)]
 pub struct JolietFileIdentifier(pub(crate) [u16; 64]);
 
-#[derive(Debug)]
 pub struct JolietDirectoryIdentifier(pub(crate) [u16; 64]);
 
 bitflags::bitflags! {
@@ -145,10 +203,49 @@ bitflags::bitflags! {
   }
 }
 
-#[derive(Debug)]
+impl FileFlags {
+  /// The existence bit; if set, the file need not be made known to the user.
+  pub fn is_hidden(&self) -> bool {
+    self.contains(FileFlags::EXISTENCE)
+  }
+
+  pub fn is_directory(&self) -> bool {
+    self.contains(FileFlags::DIRECTORY)
+  }
+
+  pub fn is_associated(&self) -> bool {
+    self.contains(FileFlags::ASSOCIATED_FILE)
+  }
+
+  /// The structure of the file's data is specified by a record format in its
+  /// extended attribute record.
+  pub fn is_record(&self) -> bool {
+    self.contains(FileFlags::RECORD)
+  }
+
+  /// Owner and group identification plus permissions are specified in the
+  /// extended attribute record.
+  pub fn is_protected(&self) -> bool {
+    self.contains(FileFlags::PROTECTION)
+  }
+
+  /// This is not the final directory record for the file.
+  pub fn is_multi_extent(&self) -> bool {
+    self.contains(FileFlags::MULTI_EXTENT)
+  }
+
+  /// This is the final (or only) directory record for the file.
+  pub fn is_final_extent(&self) -> bool {
+    !self.is_multi_extent()
+  }
+}
+
 pub struct FileIdentifier<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
 impl<const LENGTH: usize> FileIdentifier<LENGTH> {
+  /// Longest identifier this can hold.
+  pub const CAPACITY: usize = LENGTH;
+
   /// Convert from a byte slice, truncating or zero-padding as necessary.
   pub fn from_bytes_truncated(bytes: &[u8]) -> Self {
     // TODO(meowesque): Validate characters?
@@ -156,12 +253,98 @@ impl<const LENGTH: usize> FileIdentifier<LENGTH> {
     cs[..LENGTH.min(bytes.len())].copy_from_slice(&bytes[..LENGTH.min(bytes.len())]);
     Self(cs)
   }
+
+  /// The single `0x00` byte identifying a directory's own (`.`) record.
+  pub fn current_directory() -> Self {
+    Self::from_bytes_truncated(&[0x00])
+  }
+
+  /// The single `0x01` byte identifying a directory's parent (`..`) record.
+  pub fn parent_directory() -> Self {
+    Self::from_bytes_truncated(&[0x01])
+  }
 }
 
 /// `DCharacters`/`D1Characters`.
-#[derive(Debug)]
 pub struct DirectoryIdentifier<const LENGTH: usize>(pub(crate) [u8; LENGTH]);
 
+/// Render identifiers as their text, without trailing padding.
+macro_rules! impl_identifier_fmt {
+  ($($ty:ident),*) => {$(
+    impl<const LENGTH: usize> std::fmt::Display for $ty<LENGTH> {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&trim_padding(&self.0))
+      }
+    }
+
+    impl<const LENGTH: usize> std::fmt::Debug for $ty<LENGTH> {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple(stringify!($ty)).field(&self.to_string()).finish()
+      }
+    }
+  )*};
+}
+
+impl_identifier_fmt!(
+  ACharacters,
+  DCharacters,
+  A1Characters,
+  D1Characters,
+  DirectoryIdentifier
+);
+
+impl<const LENGTH: usize> std::fmt::Display for FileIdentifier<LENGTH> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match trim_padding(&self.0).as_ref() {
+      "" => f.write_str("."),
+      "\u{1}" => f.write_str(".."),
+      identifier => f.write_str(identifier),
+    }
+  }
+}
+
+impl<const LENGTH: usize> std::fmt::Debug for FileIdentifier<LENGTH> {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("FileIdentifier").field(&self.to_string()).finish()
+  }
+}
+
+/// Decode UCS-2 code units, dropping trailing padding.
+fn decode_joliet(units: &[u16]) -> String {
+  let end = units
+    .iter()
+    .rposition(|&u| u != 0 && u != b' ' as u16)
+    .map_or(0, |ix| ix + 1);
+
+  String::from_utf16_lossy(&units[..end])
+}
+
+impl std::fmt::Display for JolietFileIdentifier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&decode_joliet(&self.0))
+  }
+}
+
+impl std::fmt::Debug for JolietFileIdentifier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("JolietFileIdentifier").field(&self.to_string()).finish()
+  }
+}
+
+impl std::fmt::Display for JolietDirectoryIdentifier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&decode_joliet(&self.0))
+  }
+}
+
+impl std::fmt::Debug for JolietDirectoryIdentifier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("JolietDirectoryIdentifier")
+      .field(&self.to_string())
+      .finish()
+  }
+}
+
 /// TODO(meowesque): Define this better?
 #[derive(Debug)]
 pub struct OwnerIdentification(pub(crate) u16);
@@ -180,9 +363,9 @@ pub enum RecordFormat {
   Other(u8),
 }
 
-impl Into<u8> for RecordFormat {
-  fn into(self) -> u8 {
-    match self {
+impl From<RecordFormat> for u8 {
+  fn from(value: RecordFormat) -> Self {
+    match value {
       RecordFormat::StructureNotSpecified => 0,
       RecordFormat::FixedLengthRecords => 1,
       RecordFormat::VariableLengthRecordsMsb => 2,
@@ -202,9 +385,9 @@ pub enum RecordAttributes {
   Other(u8),
 }
 
-impl Into<u8> for RecordAttributes {
-  fn into(self) -> u8 {
-    match self {
+impl From<RecordAttributes> for u8 {
+  fn from(value: RecordAttributes) -> Self {
+    match value {
       RecordAttributes::PreceededByLfcFollowedByCrc => 0,
       RecordAttributes::FirstByteInterpretedByIso15391 => 1,
       RecordAttributes::ContainsNecessaryControlInformation => 2,
@@ -220,9 +403,9 @@ pub enum ExtendedAttributeRecordVersion {
   Other(u8),
 }
 
-impl Into<u8> for ExtendedAttributeRecordVersion {
-  fn into(self) -> u8 {
-    match self {
+impl From<ExtendedAttributeRecordVersion> for u8 {
+  fn from(value: ExtendedAttributeRecordVersion) -> Self {
+    match value {
       ExtendedAttributeRecordVersion::Standard => 1,
       ExtendedAttributeRecordVersion::Other(v) => v,
     }
@@ -248,6 +431,18 @@ pub enum StandardIdentifier {
 }
 
 impl StandardIdentifier {
+  pub fn from_bytes(bytes: &[u8; 5]) -> Self {
+    match bytes {
+      b"CD001" => StandardIdentifier::Cd001,
+      b"BEA01" => StandardIdentifier::Bea01,
+      b"NSR02" => StandardIdentifier::Nsr02,
+      b"NSR03" => StandardIdentifier::Nsr03,
+      b"BOOT2" => StandardIdentifier::Boot2,
+      b"TEA01" => StandardIdentifier::Tea01,
+      _ => StandardIdentifier::Other(*bytes),
+    }
+  }
+
   pub fn as_bytes(&self) -> &[u8; 5] {
     match self {
       StandardIdentifier::Cd001 => b"CD001",
@@ -272,9 +467,9 @@ pub enum VolumeDescriptorType {
   Terminator = 255,
 }
 
-impl Into<u8> for VolumeDescriptorType {
-  fn into(self) -> u8 {
-    match self {
+impl From<VolumeDescriptorType> for u8 {
+  fn from(value: VolumeDescriptorType) -> Self {
+    match value {
       VolumeDescriptorType::BootRecord => 0,
       VolumeDescriptorType::Primary => 1,
       VolumeDescriptorType::Supplementary => 2,
@@ -285,6 +480,19 @@ impl Into<u8> for VolumeDescriptorType {
   }
 }
 
+impl From<u8> for VolumeDescriptorType {
+  fn from(value: u8) -> Self {
+    match value {
+      0 => VolumeDescriptorType::BootRecord,
+      1 => VolumeDescriptorType::Primary,
+      2 => VolumeDescriptorType::Supplementary,
+      3 => VolumeDescriptorType::Partition,
+      255 => VolumeDescriptorType::Terminator,
+      v => VolumeDescriptorType::Other(v),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum VolumeDescriptorVersion {
@@ -292,15 +500,24 @@ pub enum VolumeDescriptorVersion {
   Other(u8),
 }
 
-impl Into<u8> for VolumeDescriptorVersion {
-  fn into(self) -> u8 {
-    match self {
+impl From<VolumeDescriptorVersion> for u8 {
+  fn from(value: VolumeDescriptorVersion) -> Self {
+    match value {
       VolumeDescriptorVersion::Standard => 1,
       VolumeDescriptorVersion::Other(v) => v,
     }
   }
 }
 
+impl From<u8> for VolumeDescriptorVersion {
+  fn from(value: u8) -> Self {
+    match value {
+      1 => VolumeDescriptorVersion::Standard,
+      v => VolumeDescriptorVersion::Other(v),
+    }
+  }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum FileStructureVersion {
@@ -308,15 +525,24 @@ pub enum FileStructureVersion {
   Other(u8),
 }
 
-impl Into<u8> for FileStructureVersion {
-  fn into(self) -> u8 {
-    match self {
+impl From<FileStructureVersion> for u8 {
+  fn from(value: FileStructureVersion) -> Self {
+    match value {
       FileStructureVersion::Standard => 1,
       FileStructureVersion::Other(v) => v,
     }
   }
 }
 
+impl From<u8> for FileStructureVersion {
+  fn from(value: u8) -> Self {
+    match value {
+      1 => FileStructureVersion::Standard,
+      v => FileStructureVersion::Other(v),
+    }
+  }
+}
+
 #[derive(Debug)]
 pub struct DigitsYear(pub(crate) u16);
 
@@ -371,6 +597,33 @@ pub struct DigitsDate {
   pub gmt_offset: NumericalGmtOffset,
 }
 
+impl DigitsDate {
+  /// A date that is not specified: all digits zero with no GMT offset.
+  pub fn unspecified() -> Self {
+    Self {
+      year: DigitsYear(0),
+      month: DigitsMonth(0),
+      day: DigitsDay(0),
+      hour: DigitsHour(0),
+      minute: DigitsMinute(0),
+      second: DigitsSecond(0),
+      hundreths: DigitsHundreths(0),
+      gmt_offset: NumericalGmtOffset(0),
+    }
+  }
+
+  pub fn is_unspecified(&self) -> bool {
+    self.year.0 == 0
+      && self.month.0 == 0
+      && self.day.0 == 0
+      && self.hour.0 == 0
+      && self.minute.0 == 0
+      && self.second.0 == 0
+      && self.hundreths.0 == 0
+      && self.gmt_offset.0 == 0
+  }
+}
+
 #[cfg(feature = "chrono")]
 impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for DigitsDate {
   fn from(dt: chrono::DateTime<Tz>) -> Self {
@@ -391,8 +644,8 @@ impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for DigitsDate {
 }
 
 #[cfg(feature = "chrono")]
-impl<Tz: chrono::TimeZone> Into<chrono::DateTime<Tz>> for DigitsDate {
-  fn into(self) -> chrono::DateTime<Tz> {
+impl<Tz: chrono::TimeZone> From<DigitsDate> for chrono::DateTime<Tz> {
+  fn from(_value: DigitsDate) -> Self {
     todo!()
   }
 }
@@ -428,8 +681,8 @@ impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for NumericalDate {
 
 
 #[cfg(feature = "chrono")]
-impl<Tz: chrono::TimeZone> Into<chrono::DateTime<Tz>> for NumericalDate {
-  fn into(self) -> chrono::DateTime<Tz> {
+impl<Tz: chrono::TimeZone> From<NumericalDate> for chrono::DateTime<Tz> {
+  fn from(_value: NumericalDate) -> Self {
     todo!()
   }
 }
@@ -465,6 +718,99 @@ pub struct PrimaryVolumeDescriptor {
   pub application_use: [u8; 512],
 }
 
+/// A field of a `PrimaryVolumeDescriptor`, for patching a serialized
+/// descriptor in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PvdField {
+  StandardIdentifier,
+  Version,
+  SystemIdentifier,
+  VolumeIdentifier,
+  VolumeSpaceSize,
+  VolumeSetSize,
+  VolumeSequenceNumber,
+  LogicalBlockSize,
+  PathTableSize,
+  TypeLPathTableLocation,
+  OptionalTypeLPathTableLocation,
+  TypeMPathTableLocation,
+  OptionalTypeMPathTableLocation,
+  RootDirectoryRecord,
+  VolumeSetIdentifier,
+  PublisherIdentifier,
+  DataPreparerIdentifier,
+  ApplicationIdentifier,
+  CopyrightFileIdentifier,
+  AbstractFileIdentifier,
+  BibliographicFileIdentifier,
+  CreationDate,
+  ModificationDate,
+  ExpirationDate,
+  EffectiveDate,
+  FileStructureVersion,
+  ApplicationUse,
+}
+
+impl PvdField {
+  /// Byte range of the field within the descriptor sector.
+  pub fn range(&self) -> std::ops::Range<usize> {
+    PrimaryVolumeDescriptor::FIELD_OFFSETS[*self as usize].1.clone()
+  }
+
+  pub fn name(&self) -> &'static str {
+    PrimaryVolumeDescriptor::FIELD_OFFSETS[*self as usize].0
+  }
+
+  /// Whether the field holds characters, padded with `FILLER`.
+  pub fn is_character_field(&self) -> bool {
+    matches!(
+      self,
+      PvdField::SystemIdentifier
+        | PvdField::VolumeIdentifier
+        | PvdField::VolumeSetIdentifier
+        | PvdField::PublisherIdentifier
+        | PvdField::DataPreparerIdentifier
+        | PvdField::ApplicationIdentifier
+        | PvdField::CopyrightFileIdentifier
+        | PvdField::AbstractFileIdentifier
+        | PvdField::BibliographicFileIdentifier
+    )
+  }
+}
+
+impl PrimaryVolumeDescriptor {
+  /// Name and byte range of every field, in `PvdField` order.
+  pub const FIELD_OFFSETS: [(&'static str, std::ops::Range<usize>); 27] = [
+    ("standard_identifier", 1..6),
+    ("version", 6..7),
+    ("system_identifier", 8..40),
+    ("volume_identifier", 40..72),
+    ("volume_space_size", 80..88),
+    ("volume_set_size", 120..124),
+    ("volume_sequence_number", 124..128),
+    ("logical_block_size", 128..132),
+    ("path_table_size", 132..140),
+    ("type_l_path_table_location", 140..144),
+    ("optional_type_l_path_table_location", 144..148),
+    ("type_m_path_table_location", 148..152),
+    ("optional_type_m_path_table_location", 152..156),
+    ("root_directory_record", 156..190),
+    ("volume_set_identifier", 190..318),
+    ("publisher_identifier", 318..446),
+    ("data_preparer_identifier", 446..574),
+    ("application_identifier", 574..702),
+    ("copyright_file_identifier", 702..739),
+    ("abstract_file_identifier", 739..776),
+    ("bibliographic_file_identifier", 776..813),
+    ("creation_date", 813..830),
+    ("modification_date", 830..847),
+    ("expiration_date", 847..864),
+    ("effective_date", 864..881),
+    ("file_structure_version", 881..882),
+    ("application_use", 883..1395),
+  ];
+}
+
 #[derive(Debug)]
 pub struct SupplementaryVolumeDescriptor {
   pub standard_identifier: StandardIdentifier,
@@ -511,6 +857,14 @@ pub struct VolumePartitionDescriptor {
 #[derive(Debug)]
 pub struct VolumeDescriptorSetTerminator;
 
+/// A volume descriptor of a type this crate parses.
+#[derive(Debug)]
+pub enum VolumeDescriptor {
+  Primary(PrimaryVolumeDescriptor),
+  Supplementary(SupplementaryVolumeDescriptor),
+  Terminator(VolumeDescriptorSetTerminator),
+}
+
 #[derive(Debug)]
 pub struct DirectoryRecord<Ext: Extension> {
   pub length: u8,
@@ -524,6 +878,71 @@ pub struct DirectoryRecord<Ext: Extension> {
   pub volume_sequence_number: u16,
   pub file_identifier_length: u8,
   pub file_identifier: Ext::FileIdentifier,
+  /// System Use area following the identifier and its padding, such as
+  /// SUSP entries, carried through as is.
+  pub system_use: Vec<u8>,
+}
+
+impl<Ext: Extension> DirectoryRecord<Ext> {
+  /// Largest length a directory record can declare.
+  pub const MAX_LENGTH: usize = 255;
+
+  /// System Use bytes left in a record whose identifier is `name_len` bytes
+  /// long. Anything beyond this must spill into a continuation area.
+  pub fn capacity_for(name_len: usize) -> usize {
+    // A padding byte follows identifiers of even length.
+    let used = 33 + name_len + name_len.is_multiple_of(2) as usize;
+    Self::MAX_LENGTH.saturating_sub(used)
+  }
+
+  /// Replace the file identifier, updating the record and identifier lengths
+  /// to match.
+  pub fn with_identifier(self, file_identifier: Ext::FileIdentifier) -> Self {
+    use crate::serialize::IsoSerialize;
+
+    let identifier_length = file_identifier.extent();
+
+    Self {
+      // A padding byte follows identifiers of even length.
+      length: (33
+        + identifier_length
+        + identifier_length.is_multiple_of(2) as usize
+        + self.system_use.len()) as u8,
+      file_identifier_length: identifier_length as u8,
+      file_identifier,
+      ..self
+    }
+  }
+}
+
+impl<Ext: Extension> DirectoryRecord<Ext> {
+  pub fn is_hidden(&self) -> bool {
+    self.file_flags.is_hidden()
+  }
+
+  pub fn is_directory(&self) -> bool {
+    self.file_flags.is_directory()
+  }
+
+  pub fn is_associated(&self) -> bool {
+    self.file_flags.is_associated()
+  }
+
+  pub fn is_record(&self) -> bool {
+    self.file_flags.is_record()
+  }
+
+  pub fn is_protected(&self) -> bool {
+    self.file_flags.is_protected()
+  }
+
+  pub fn is_multi_extent(&self) -> bool {
+    self.file_flags.is_multi_extent()
+  }
+
+  pub fn is_final_extent(&self) -> bool {
+    self.file_flags.is_final_extent()
+  }
 }
 
 /// Root directory record as found in `SupplementaryVolumeDescriptor` and
@@ -571,9 +990,9 @@ pub enum ElToritoHeaderId {
   Other(u8),
 }
 
-impl Into<u8> for ElToritoHeaderId {
-  fn into(self) -> u8 {
-    match self {
+impl From<ElToritoHeaderId> for u8 {
+  fn from(value: ElToritoHeaderId) -> Self {
+    match value {
       ElToritoHeaderId::Standard => 1,
       ElToritoHeaderId::Other(v) => v,
     }
@@ -589,9 +1008,9 @@ pub enum ElToritoPlatformId {
   Other(u8),
 }
 
-impl Into<u8> for ElToritoPlatformId {
-  fn into(self) -> u8 {
-    match self {
+impl From<ElToritoPlatformId> for u8 {
+  fn from(value: ElToritoPlatformId) -> Self {
+    match value {
       ElToritoPlatformId::X86 => 0,
       ElToritoPlatformId::PowerPc => 1,
       ElToritoPlatformId::Mac => 2,
@@ -608,9 +1027,9 @@ pub enum ElToritoBootIndicator {
   Other(u8),
 }
 
-impl Into<u8> for ElToritoBootIndicator {
-  fn into(self) -> u8 {
-    match self {
+impl From<ElToritoBootIndicator> for u8 {
+  fn from(value: ElToritoBootIndicator) -> Self {
+    match value {
       ElToritoBootIndicator::Bootable => 0x88,
       ElToritoBootIndicator::NonBootable => 0x00,
       ElToritoBootIndicator::Other(v) => v,
@@ -632,9 +1051,9 @@ bitflags::bitflags! {
 #[derive(Debug, Clone, Copy)]
 pub struct ElToritoBootMediaType(pub(crate) u8);
 
-impl Into<u8> for ElToritoBootMediaType {
-  fn into(self) -> u8 {
-    self.0
+impl From<ElToritoBootMediaType> for u8 {
+  fn from(value: ElToritoBootMediaType) -> Self {
+    value.0
   }
 }
 
@@ -648,9 +1067,9 @@ pub enum ElToritoEmulationType {
   HardDisk = 4,
 }
 
-impl Into<u8> for ElToritoEmulationType {
-  fn into(self) -> u8 {
-    match self {
+impl From<ElToritoEmulationType> for u8 {
+  fn from(value: ElToritoEmulationType) -> Self {
+    match value {
       ElToritoEmulationType::NoEmulation => 0,
       ElToritoEmulationType::Floppy12M => 1,
       ElToritoEmulationType::Floppy144M => 2,
@@ -686,9 +1105,9 @@ pub enum ElToritoSelectionCriteriaType {
   Other(u8),
 }
 
-impl Into<u8> for ElToritoSelectionCriteriaType {
-  fn into(self) -> u8 {
-    match self {
+impl From<ElToritoSelectionCriteriaType> for u8 {
+  fn from(value: ElToritoSelectionCriteriaType) -> Self {
+    match value {
       ElToritoSelectionCriteriaType::NoSelectionCriteria => 0,
       ElToritoSelectionCriteriaType::LanguageAndVersionInformation => 1,
       ElToritoSelectionCriteriaType::Other(v) => v,
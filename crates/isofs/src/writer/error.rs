@@ -4,4 +4,22 @@ pub enum Error {
   Serialize(#[from] crate::serialize::IsoSerializeError),
   #[error("I/O error: {0}")]
   Io(#[from] std::io::Error),
+  #[error("The {field} names `{name}`, which is not a file in the root directory")]
+  MissingReferencedFile { field: &'static str, name: String },
+  #[error("No file is staged at `{0}` to associate with")]
+  MissingDataFile(String),
+  #[error("A file is already staged at `{0}`")]
+  FileExists(String),
+  #[error("`{name}` is not a valid identifier: {violation}")]
+  InvalidIdentifier {
+    name: String,
+    violation: crate::identifier::IdentifierViolation,
+  },
+  #[error("File version {0} is outside of 1..=32767")]
+  InvalidFileVersion(u16),
+  #[error("Volume sequence number {volume_sequence_number} is outside a volume set of size {volume_set_size}")]
+  InvalidVolumeSequence {
+    volume_set_size: u16,
+    volume_sequence_number: u16,
+  },
 }
@@ -1,25 +1,39 @@
-use std::path::{Path, PathBuf};
+use std::{
+  cell::RefCell,
+  path::{Path, PathBuf},
+  rc::Rc,
+};
 
-use crate::spec;
+use crate::{identifier::InterchangeLevel, serialize::IsoSerialize, spec};
+
+use super::WriterExtension;
 
 pub trait EntryLike {
   fn extent_lba(&self) -> Option<u32>;
 
   fn set_extent_lba(&mut self, lba: u32);
 
-  fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension>;
+  fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext>;
 
   fn assign_extent_lba(&mut self, allocator: &mut super::lba::LbaAllocator) {
-    let descriptor = self.descriptor();
-    self.set_extent_lba(allocator.allocate(descriptor.data_length));
+    // The data length does not depend on the identifier encoding.
+    let descriptor = self.descriptor::<spec::NoExtension>();
+    self.set_extent_lba(allocator.allocate_bytes(descriptor.data_length));
   }
 }
 
 pub trait DirectoryLike: EntryLike {
-  fn entries_iter(&self) -> impl Iterator<Item = &Entry>; 
+  fn entries_iter(&self) -> impl Iterator<Item = &Entry>;
 
   fn entries_mut(&mut self) -> &mut Vec<Entry>;
 
+  /// Record the sector-packed size of this directory's records.
+  fn set_data_length(&mut self, data_length: u32);
+
+  fn find(&self, name: &str) -> Option<&Entry> {
+    self.entries_iter().find(|e| e.name() == name)
+  }
+
   fn find_mut(&mut self, name: &str) -> Option<&mut Entry> {
     self.entries_mut().iter_mut().find(|e| e.name() == name)
   }
@@ -39,15 +53,228 @@ pub trait DirectoryLike: EntryLike {
     }
   }
 
-  fn assign_extent_lbas(&mut self, allocator: &mut super::lba::LbaAllocator) {
-    self.set_extent_lba(allocator.allocate(self.descriptor().data_length));
+  /// Assign extents to this directory and everything beneath it, packing
+  /// directory records as they are written with `Ext`.
+  fn assign_extent_lbas<Ext: WriterExtension>(&mut self, allocator: &mut super::lba::LbaAllocator) {
+    let data_length = packed_directory_length::<Ext>(self.entries_iter(), allocator.sector_size());
+    self.set_data_length(data_length);
+    self.set_extent_lba(allocator.allocate_bytes(data_length));
 
     for entry in self.entries_mut() {
-      entry.assign_extent_lba(allocator);
+      match entry {
+        Entry::File(file) => {
+          file.assign_extent_lba(allocator);
+
+          if let Some(associated) = &mut file.associated {
+            associated.assign_extent_lba(allocator);
+          }
+        }
+        // Allocates its own extent once its packed length is known.
+        Entry::Directory(dir) => dir.assign_extent_lbas::<Ext>(allocator),
+      }
+    }
+  }
+}
 
-      if let Entry::Directory(dir) = entry {
-        dir.assign_extent_lbas(allocator);
+/// Name of the translation table generated in each directory when
+/// `WriterOptions::trans_tbl` is set.
+pub const TRANS_TBL: &str = "TRANS.TBL";
+
+/// Stage a `TRANS.TBL` in `directory` and all of its subdirectories, listing
+/// an `F <identifier> <name>` or `D <identifier> <name>` line per entry.
+fn upsert_trans_tbls<D>(directory: &mut D, omit_version: bool)
+where
+  D: DirectoryLike,
+{
+  let table = directory
+    .entries_iter()
+    .filter(|e| e.name() != TRANS_TBL)
+    .map(|e| match e {
+      Entry::File(file) => format!("F {} {}\n", file.identifier(), file.name),
+      Entry::Directory(dir) => format!("D {} {}\n", dir.identifier(), dir.name),
+    })
+    .collect::<String>()
+    .into_bytes();
+
+  for entry in directory.entries_mut() {
+    if let Entry::Directory(dir) = entry {
+      upsert_trans_tbls(dir, omit_version);
+    }
+  }
+
+  let length = table.len() as u64;
+  let mut file = FileEntry::from_reader(TRANS_TBL.to_string(), std::io::Cursor::new(table), length);
+  file.omit_version = omit_version;
+
+  directory.upsert(Entry::File(file));
+}
+
+/// Name of the root directory deep directories are moved into when
+/// `WriterOptions::deep_relocation` is set.
+pub const RR_MOVED: &str = "RR_MOVED";
+
+/// Deepest directory level ECMA-119 allows, counting the root as level 1.
+const MAX_DIRECTORY_DEPTH: usize = 8;
+
+/// Detach the directories that would sit below `MAX_DIRECTORY_DEPTH` from
+/// `entries`, the contents of a directory at level `depth`, into `moved`.
+fn detach_deep_directories(
+  entries: &mut Vec<Entry>,
+  depth: usize,
+  moved: &mut Vec<DirectoryEntry>,
+) {
+  if depth >= MAX_DIRECTORY_DEPTH {
+    for entry in std::mem::take(entries) {
+      match entry {
+        Entry::Directory(dir) => moved.push(dir),
+        entry => entries.push(entry),
+      }
+    }
+
+    return;
+  }
+
+  for entry in entries.iter_mut() {
+    if let Entry::Directory(dir) = entry {
+      detach_deep_directories(&mut dir.entries, depth + 1, moved);
+    }
+  }
+}
+
+/// Apply `f` to every file in `entries` and their subdirectories, including
+/// associated files.
+fn for_each_file_mut(entries: &mut [Entry], f: &mut impl FnMut(&mut FileEntry)) {
+  for entry in entries {
+    match entry {
+      Entry::File(file) => {
+        if let Some(associated) = &mut file.associated {
+          f(associated);
+        }
+
+        f(file);
       }
+      Entry::Directory(dir) => for_each_file_mut(&mut dir.entries, f),
+    }
+  }
+}
+
+/// Sanitize the identifiers of `entries` and everything beneath them for
+/// `level`.
+fn set_interchange_level(entries: &mut [Entry], level: InterchangeLevel) {
+  for entry in entries {
+    match entry {
+      Entry::File(file) => {
+        if let Some(associated) = &mut file.associated {
+          associated.interchange_level = level;
+        }
+
+        file.interchange_level = level;
+      }
+      Entry::Directory(dir) => {
+        dir.interchange_level = level;
+        set_interchange_level(&mut dir.entries, level);
+      }
+    }
+  }
+}
+
+/// Give every entry in `entries`, and beneath them, an identifier distinct
+/// from its siblings'. Entries keep their staged order, and any whose
+/// identifier is already taken has the end of its name replaced with `_1`,
+/// `_2` and so on until it is unique.
+fn make_identifiers_unique(entries: &mut [Entry]) {
+  let mut taken = std::collections::HashSet::new();
+
+  for entry in entries.iter_mut() {
+    let mut suffix = None;
+    entry.set_collision_suffix(suffix);
+
+    while !taken.insert(entry.identifier()) {
+      let n = suffix.map_or(1, |n| n + 1);
+      suffix = Some(n);
+      entry.set_collision_suffix(suffix);
+    }
+
+    if let Entry::Directory(dir) = entry {
+      make_identifiers_unique(&mut dir.entries);
+    }
+  }
+}
+
+/// Check that every entry in `entries`, and beneath them, is staged under a
+/// name already valid at `level`, files together with their version.
+fn validate_identifiers(
+  entries: &[Entry],
+  level: InterchangeLevel,
+) -> Result<(), super::error::Error> {
+  for entry in entries {
+    let validated = match entry {
+      Entry::File(file) => crate::identifier::validate_identifier(
+        &format!("{};{}", file.name, file.version.unwrap_or(1)),
+        level,
+      ),
+      Entry::Directory(dir) => crate::identifier::validate_directory_identifier(&dir.name, level),
+    };
+
+    validated.map_err(|violation| super::error::Error::InvalidIdentifier {
+      name: entry.name().to_string(),
+      violation,
+    })?;
+
+    if let Entry::Directory(dir) = entry {
+      validate_identifiers(&dir.entries, level)?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Sort `entries` and those of their subdirectories into the order ECMA-119
+/// requires of directory records.
+fn sort_entries(entries: &mut [Entry]) {
+  entries.sort_by(|a, b| crate::identifier::identifier_cmp(&a.identifier(), &b.identifier()));
+
+  for entry in entries {
+    if let Entry::Directory(dir) = entry {
+      sort_entries(&mut dir.entries);
+    }
+  }
+}
+
+/// Source of a staged file's content, read when the image is written.
+pub enum FileContent {
+  /// A file on the host filesystem.
+  Handle(std::fs::File, std::fs::Metadata),
+  /// An arbitrary reader yielding exactly `length` bytes.
+  ///
+  /// The length must be known up front since extents are allocated before
+  /// any content is written. The reader is consumed by the first write.
+  Reader(Rc<RefCell<dyn std::io::Read>>, u64),
+}
+
+impl std::fmt::Debug for FileContent {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      FileContent::Handle(handle, metadata) => f
+        .debug_tuple("Handle")
+        .field(handle)
+        .field(metadata)
+        .finish(),
+      FileContent::Reader(_, length) => f.debug_tuple("Reader").field(length).finish(),
+    }
+  }
+}
+
+impl FileContent {
+  pub fn from_reader(reader: impl std::io::Read + 'static, length: u64) -> Self {
+    FileContent::Reader(Rc::new(RefCell::new(reader)), length)
+  }
+
+  /// Size of the content in bytes.
+  pub fn size(&self) -> u64 {
+    match self {
+      FileContent::Handle(_, metadata) => metadata.len(),
+      FileContent::Reader(_, length) => *length,
     }
   }
 }
@@ -56,8 +283,21 @@ pub trait DirectoryLike: EntryLike {
 pub struct FileEntry {
   pub(crate) extent_lba: Option<u32>,
   name: String,
-  metadata: std::fs::Metadata,
-  pub(crate) handle: std::fs::File,
+  pub(crate) content: FileContent,
+  /// Host path the content is read from, if any.
+  source: Option<PathBuf>,
+  /// Associated file recorded immediately before this one under the same
+  /// name, such as a resource fork.
+  pub(crate) associated: Option<Box<FileEntry>>,
+  /// Version number written after separator 2, `;1` if unset.
+  version: Option<u16>,
+  /// Record the identifier without separator 2 and a version.
+  omit_version: bool,
+  /// Interchange level the name is sanitized for.
+  interchange_level: InterchangeLevel,
+  /// Suffix telling the identifier apart from a sibling's that sanitizes to
+  /// the same text, see `identifier::suffix_name`.
+  collision_suffix: Option<u32>,
 }
 
 impl EntryLike for FileEntry {
@@ -69,21 +309,36 @@ impl EntryLike for FileEntry {
     self.extent_lba = Some(lba);
   }
 
-  fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension> {
+  fn assign_extent_lba(&mut self, allocator: &mut super::lba::LbaAllocator) {
+    let lba = match self.content.size() {
+      0 => allocator.allocate_empty(),
+      size => allocator.allocate_bytes(size as u32),
+    };
+
+    self.set_extent_lba(lba);
+  }
+
+  fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext> {
+    let identifier = self.identifier();
+
+    // Lengths are derived from the stored identifier by `with_identifier`,
+    // so names longer than the identifier field stay consistent.
     spec::DirectoryRecord {
-      length: 33 + self.name.len() as u8 + (self.name.len() % 2 == 0) as u8,
+      length: 0,
       extended_attribute_length: 0,
       extent_location: self.extent_lba.unwrap_or(0),
-      data_length: self.metadata.len() as u32,
+      data_length: self.content.size() as u32,
       // TODO(meowesque): Time handling?
       recording_date: chrono::Utc::now().into(),
       file_flags: spec::FileFlags::empty(),
       file_unit_size: 0,
       interleave_gap_size: 0,
       volume_sequence_number: 1,
-      file_identifier_length: self.name.len() as u8,
-      file_identifier: spec::FileIdentifier::from_bytes_truncated(self.name.as_bytes()),
+      file_identifier_length: 0,
+      file_identifier: Ext::current_directory(),
+      system_use: vec![],
     }
+    .with_identifier(Ext::file_identifier(&identifier))
   }
 }
 
@@ -93,12 +348,88 @@ impl FileEntry {
     let metadata = handle.metadata()?;
 
     Ok(Self {
+      source: Some(source.as_ref().to_path_buf()),
+      ..Self::from_content(name, FileContent::Handle(handle, metadata))
+    })
+  }
+
+  pub fn from_reader(name: String, reader: impl std::io::Read + 'static, length: u64) -> Self {
+    Self::from_content(name, FileContent::from_reader(reader, length))
+  }
+
+  pub fn from_content(name: String, content: FileContent) -> Self {
+    Self {
       extent_lba: None,
       name,
-      metadata,
-      handle,
-    })
+      content,
+      source: None,
+      associated: None,
+      version: None,
+      omit_version: false,
+      interchange_level: InterchangeLevel::Level1,
+      collision_suffix: None,
+    }
   }
+
+  /// Identifier recorded for this file: its name as sanitized by
+  /// `sanitize_identifier`, with its version unless omitted.
+  pub(crate) fn identifier(&self) -> String {
+    let sanitized = crate::identifier::sanitize_identifier(&self.name, self.interchange_level);
+    let mut parts = crate::identifier::IdentifierParts::parse(&sanitized);
+
+    let suffixed;
+    if let Some(n) = self.collision_suffix {
+      let extension = parts.extension.unwrap_or_default();
+      let max_length = self.interchange_level.max_file_name_length(extension.len());
+      suffixed = crate::identifier::suffix_name(parts.name, n, max_length);
+      parts.name = &suffixed;
+    }
+
+    parts.version = match self.omit_version {
+      true => None,
+      false => Some(self.version.unwrap_or(1)),
+    };
+
+    parts.to_identifier()
+  }
+}
+
+/// Lengths of a directory's `.` and `..` records as written with `Ext`.
+fn special_record_lengths<Ext: WriterExtension>() -> [u64; 2] {
+  [Ext::current_directory(), Ext::parent_directory()].map(|identifier| {
+    // A padding byte follows identifiers of even length.
+    (33 + identifier.extent() + identifier.extent().is_multiple_of(2) as usize) as u64
+  })
+}
+
+/// Size of a directory's records, including its `.` and `..` records.
+fn directory_data_length<Ext: WriterExtension>(entries: &[Entry]) -> u32 {
+  // TODO(meowesque): This seems inefficient.
+  let special_records = special_record_lengths::<Ext>().iter().sum::<u64>() as u32;
+
+  special_records
+    + entries
+      .iter()
+      .flat_map(|e| e.descriptors::<Ext>())
+      .map(|d| d.length as u32)
+      .sum::<u32>()
+}
+
+/// Size of a directory's records once packed into whole sectors the way
+/// `SectorWriter` writes them, including its `.` and `..` records.
+fn packed_directory_length<'a, Ext: WriterExtension>(
+  entries: impl Iterator<Item = &'a Entry>,
+  sector_size: u32,
+) -> u32 {
+  let special_records = special_record_lengths::<Ext>();
+  let records = entries
+    .flat_map(|e| e.descriptors::<Ext>())
+    .map(|d| d.length as u64);
+
+  super::sector::packed_size(
+    special_records.into_iter().chain(records),
+    sector_size as u64,
+  ) as u32
 }
 
 #[derive(Debug)]
@@ -106,6 +437,31 @@ pub struct DirectoryEntry {
   extent_lba: Option<u32>,
   name: String,
   entries: Vec<Entry>,
+  /// Sector-packed size, known once extents are assigned.
+  data_length: Option<u32>,
+  /// Interchange level the name is sanitized for.
+  interchange_level: InterchangeLevel,
+  /// Suffix telling the identifier apart from a sibling's that sanitizes to
+  /// the same text, see `identifier::suffix_name`.
+  collision_suffix: Option<u32>,
+}
+
+impl DirectoryEntry {
+  /// Identifier recorded for this directory: its name as sanitized by
+  /// `sanitize_directory_identifier`, never carrying a version.
+  pub(crate) fn identifier(&self) -> String {
+    let identifier =
+      crate::identifier::sanitize_directory_identifier(&self.name, self.interchange_level);
+
+    match self.collision_suffix {
+      Some(n) => crate::identifier::suffix_name(
+        &identifier,
+        n,
+        self.interchange_level.max_directory_length(),
+      ),
+      None => identifier,
+    }
+  }
 }
 
 impl EntryLike for DirectoryEntry {
@@ -117,17 +473,14 @@ impl EntryLike for DirectoryEntry {
     self.extent_lba = Some(lba);
   }
 
-  fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension> {
+  fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext> {
     spec::DirectoryRecord {
-      length: 33 + self.name.len() as u8 + (self.name.len() % 2 == 0) as u8,
+      length: 0,
       extended_attribute_length: 0,
       extent_location: self.extent_lba.unwrap_or(0),
-      // TODO(meowesque): This seems inefficient.
       data_length: self
-        .entries
-        .iter()
-        .map(|e| e.descriptor().length as u32)
-        .sum(),
+        .data_length
+        .unwrap_or_else(|| directory_data_length::<Ext>(&self.entries)),
       // TODO(meowesque): Time handling?
       recording_date: chrono::Utc::now().into(),
       file_flags: spec::FileFlags::DIRECTORY,
@@ -135,9 +488,11 @@ impl EntryLike for DirectoryEntry {
       interleave_gap_size: 0,
       // TODO(meowesque): Support multi-volume?
       volume_sequence_number: 1,
-      file_identifier_length: self.name.len() as u8,
-      file_identifier: spec::FileIdentifier::from_bytes_truncated(self.name.as_bytes()),
+      file_identifier_length: 0,
+      file_identifier: Ext::current_directory(),
+      system_use: vec![],
     }
+    .with_identifier(Ext::file_identifier(&self.identifier()))
   }
 }
 
@@ -149,6 +504,10 @@ impl DirectoryLike for DirectoryEntry {
   fn entries_mut(&mut self) -> &mut Vec<Entry> {
     &mut self.entries
   }
+
+  fn set_data_length(&mut self, data_length: u32) {
+    self.data_length = Some(data_length);
+  }
 }
 
 #[derive(Debug)]
@@ -172,15 +531,57 @@ impl EntryLike for Entry {
     }
   }
 
-  fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension> {
+  fn assign_extent_lba(&mut self, allocator: &mut super::lba::LbaAllocator) {
+    match self {
+      Entry::File(x) => x.assign_extent_lba(allocator),
+      Entry::Directory(x) => x.assign_extent_lba(allocator),
+    }
+  }
+
+  fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext> {
     match self {
-      Entry::File(x) => x.descriptor(),
-      Entry::Directory(x) => x.descriptor(),
+      Entry::File(x) => x.descriptor::<Ext>(),
+      Entry::Directory(x) => x.descriptor::<Ext>(),
     }
   }
 }
 
+/// What will be written for a staged entry.
+#[derive(Debug)]
+pub struct EntryMetadata<'a> {
+  /// Content size for files, or the size of the directory's records.
+  pub size: u64,
+  pub is_dir: bool,
+  /// Last modification time of the host file, if known.
+  pub modified: Option<std::time::SystemTime>,
+  /// Host path a file's content is read from, if any.
+  pub source: Option<&'a Path>,
+}
+
 impl Entry {
+  pub fn metadata(&self) -> EntryMetadata<'_> {
+    match self {
+      Entry::File(x) => EntryMetadata {
+        size: x.content.size(),
+        is_dir: false,
+        modified: match &x.content {
+          FileContent::Handle(_, metadata) => metadata.modified().ok(),
+          FileContent::Reader(..) => None,
+        },
+        source: x.source.as_deref(),
+      },
+      Entry::Directory(x) => EntryMetadata {
+        size: x
+          .data_length
+          .unwrap_or_else(|| directory_data_length::<spec::NoExtension>(&x.entries))
+          as u64,
+        is_dir: true,
+        modified: None,
+        source: None,
+      },
+    }
+  }
+
   pub fn name(&self) -> &str {
     match self {
       Entry::File(x) => &x.name,
@@ -188,21 +589,65 @@ impl Entry {
     }
   }
 
-  pub(crate) fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension> {
+  fn set_collision_suffix(&mut self, suffix: Option<u32>) {
+    match self {
+      Entry::File(x) => {
+        // The associated file is recorded under the same identifier.
+        if let Some(associated) = &mut x.associated {
+          associated.collision_suffix = suffix;
+        }
+
+        x.collision_suffix = suffix;
+      }
+      Entry::Directory(x) => x.collision_suffix = suffix,
+    }
+  }
+
+  /// Identifier recorded for this entry.
+  pub(crate) fn identifier(&self) -> String {
     match self {
-      Entry::File(x) => x.descriptor(),
-      Entry::Directory(x) => x.descriptor(),
+      Entry::File(x) => x.identifier(),
+      Entry::Directory(x) => x.identifier(),
     }
   }
+
+  pub(crate) fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext> {
+    match self {
+      Entry::File(x) => x.descriptor::<Ext>(),
+      Entry::Directory(x) => x.descriptor::<Ext>(),
+    }
+  }
+
+  /// Directory records for this entry, led by its associated file's record.
+  pub(crate) fn descriptors<Ext: WriterExtension>(
+    &self,
+  ) -> impl Iterator<Item = spec::DirectoryRecord<Ext>> {
+    let associated = match self {
+      Entry::File(FileEntry {
+        associated: Some(associated),
+        ..
+      }) => {
+        let mut descriptor = associated.descriptor::<Ext>();
+        descriptor.file_flags |= spec::FileFlags::ASSOCIATED_FILE;
+        Some(descriptor)
+      }
+      _ => None,
+    };
+
+    associated
+      .into_iter()
+      .chain(std::iter::once(self.descriptor::<Ext>()))
+  }
 }
 
 #[derive(Default, Debug)]
 pub struct RootDirectory {
   pub extent_lba: Option<u32>,
   pub entries: Vec<Entry>,
+  /// Sector-packed size, known once extents are assigned.
+  data_length: Option<u32>,
 }
 
-
 impl EntryLike for RootDirectory {
   fn extent_lba(&self) -> Option<u32> {
     self.extent_lba
@@ -212,17 +657,14 @@ impl EntryLike for RootDirectory {
     self.extent_lba = Some(lba);
   }
 
-  fn descriptor(&self) -> spec::DirectoryRecord<spec::NoExtension> {
+  fn descriptor<Ext: WriterExtension>(&self) -> spec::DirectoryRecord<Ext> {
     spec::DirectoryRecord {
-      length: 34 as u8,
+      length: 34,
       extended_attribute_length: 0,
       extent_location: self.extent_lba.unwrap_or(0),
-      // TODO(meowesque): This seems inefficient.
       data_length: self
-        .entries
-        .iter()
-        .map(|e| e.descriptor().length as u32)
-        .sum(),
+        .data_length
+        .unwrap_or_else(|| directory_data_length::<Ext>(&self.entries)),
       // TODO(meowesque): Time handling?
       recording_date: chrono::Utc::now().into(),
       file_flags: spec::FileFlags::DIRECTORY,
@@ -231,8 +673,10 @@ impl EntryLike for RootDirectory {
       // TODO(meowesque): Support multi-volume?
       volume_sequence_number: 1,
       file_identifier_length: 1,
-      file_identifier: spec::FileIdentifier::from_bytes_truncated(&[0]),
+      file_identifier: Ext::current_directory(),
+      system_use: vec![],
     }
+    .with_identifier(Ext::current_directory())
   }
 }
 
@@ -244,6 +688,10 @@ impl DirectoryLike for RootDirectory {
   fn entries_mut(&mut self) -> &mut Vec<Entry> {
     &mut self.entries
   }
+
+  fn set_data_length(&mut self, data_length: u32) {
+    self.data_length = Some(data_length);
+  }
 }
 
 impl RootDirectory {
@@ -251,10 +699,8 @@ impl RootDirectory {
     spec::RootDirectoryRecord {
       extent_location: self.extent_lba.unwrap_or(0),
       data_length: self
-        .entries
-        .iter()
-        .map(|e| e.descriptor().length as u32)
-        .sum(),
+        .data_length
+        .unwrap_or_else(|| directory_data_length::<spec::NoExtension>(&self.entries)),
       recording_date: chrono::Utc::now().into(),
       file_flags: spec::FileFlags::DIRECTORY,
       file_unit_size: 0,
@@ -264,14 +710,142 @@ impl RootDirectory {
   }
 }
 
+/// What `Filesystem::add_tree_with_policy` does with a file that is already
+/// staged at the same destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+  /// Replace the staged file, as `upsert_file` does.
+  Overwrite,
+  /// Keep the staged file.
+  Skip,
+  /// Fail with `Error::FileExists`.
+  Error,
+}
+
 #[derive(Default, Debug)]
 pub struct Filesystem {
   pub root: RootDirectory,
 }
 
 impl Filesystem {
-  pub(crate) fn assign_extent_lbas(&mut self, allocator: &mut super::lba::LbaAllocator) {
-    self.root.assign_extent_lbas(allocator);
+  /// Every staged entry with its full destination path, depth first and in
+  /// the order entries were staged.
+  pub fn iter(&self) -> impl Iterator<Item = (PathBuf, &Entry)> {
+    let mut stack = self
+      .root
+      .entries
+      .iter()
+      .rev()
+      .map(|e| (PathBuf::from(e.name()), e))
+      .collect::<Vec<_>>();
+
+    std::iter::from_fn(move || {
+      let (path, entry) = stack.pop()?;
+
+      if let Entry::Directory(dir) = entry {
+        stack.extend(dir.entries.iter().rev().map(|e| (path.join(e.name()), e)));
+      }
+
+      Some((path, entry))
+    })
+  }
+
+  pub(crate) fn assign_extent_lbas<Ext: WriterExtension>(
+    &mut self,
+    allocator: &mut super::lba::LbaAllocator,
+  ) {
+    self.root.assign_extent_lbas::<Ext>(allocator);
+  }
+
+  /// Move every directory nested deeper than ECMA-119 allows into
+  /// `RR_MOVED` in the root, keeping its contents. Directories that are still
+  /// too deep within `RR_MOVED` are moved alongside them. Moved directories
+  /// keep their names and are never merged with one another or with what
+  /// `RR_MOVED` already holds; identifiers that clash once sanitized are told
+  /// apart by `make_identifiers_unique`.
+  pub(crate) fn relocate_deep_directories(&mut self) {
+    let mut moved = vec![];
+    detach_deep_directories(&mut self.root.entries, 1, &mut moved);
+
+    if moved.is_empty() {
+      return;
+    }
+
+    let mut ix = 0;
+
+    while ix < moved.len() {
+      // Moved directories sit at level 3, beneath `RR_MOVED`.
+      let mut entries = std::mem::take(&mut moved[ix].entries);
+      detach_deep_directories(&mut entries, 3, &mut moved);
+      moved[ix].entries = entries;
+      ix += 1;
+    }
+
+    let moved = moved.into_iter().map(Entry::Directory);
+
+    match self.root.find_mut(RR_MOVED) {
+      Some(Entry::Directory(rr_moved)) => rr_moved.entries.extend(moved),
+      _ => self.root.entries.push(Entry::Directory(DirectoryEntry {
+        extent_lba: None,
+        name: RR_MOVED.to_string(),
+        entries: moved.collect(),
+        data_length: None,
+        interchange_level: InterchangeLevel::Level1,
+        collision_suffix: None,
+      })),
+    }
+  }
+
+  /// The root directory file `name` refers to, matching either its staged
+  /// name or its recorded identifier, with or without the version.
+  pub(crate) fn find_root_file(&self, name: &str) -> Option<&FileEntry> {
+    self.root.entries.iter().find_map(|entry| match entry {
+      Entry::File(file) => {
+        let identifier = file.identifier();
+        let mut unversioned = crate::identifier::IdentifierParts::parse(&identifier);
+        unversioned.version = None;
+
+        (file.name == name || identifier == name || unversioned.to_identifier() == name)
+          .then_some(file)
+      }
+      Entry::Directory(_) => None,
+    })
+  }
+
+  /// Sort every directory's entries by their recorded identifiers.
+  pub(crate) fn sort_entries(&mut self) {
+    sort_entries(&mut self.root.entries);
+  }
+
+  /// Sanitize every staged file and directory identifier for `level`.
+  pub(crate) fn set_interchange_level(&mut self, level: InterchangeLevel) {
+    set_interchange_level(&mut self.root.entries, level);
+  }
+
+  /// Reject names that would need sanitizing at `level`, as described on
+  /// `validate_identifiers`.
+  pub(crate) fn validate_identifiers(
+    &self,
+    level: InterchangeLevel,
+  ) -> Result<(), super::error::Error> {
+    validate_identifiers(&self.root.entries, level)
+  }
+
+  /// Tell apart sibling identifiers that sanitize to the same text, as
+  /// described on `make_identifiers_unique`.
+  pub(crate) fn make_identifiers_unique(&mut self) {
+    make_identifiers_unique(&mut self.root.entries);
+  }
+
+  /// Record every staged file without its `;version` suffix.
+  pub(crate) fn omit_versions(&mut self) {
+    for_each_file_mut(&mut self.root.entries, &mut |file| file.omit_version = true);
+  }
+
+  /// Stage a `TRANS.TBL` in every directory, itself recorded without a
+  /// version if `omit_version` is set.
+  pub(crate) fn upsert_trans_tbls(&mut self, omit_version: bool) {
+    upsert_trans_tbls(&mut self.root, omit_version);
   }
 
   pub fn upsert_file(
@@ -280,28 +854,160 @@ impl Filesystem {
     source: impl AsRef<Path>,
   ) -> Result<(), super::error::Error> {
     let destination = destination.as_ref();
-    let components = destination.components();
-
-    let mut tail = Entry::File(FileEntry::new(
-      // TODO(meowesque): Handle error more gracefully.
-      destination
-        .file_name()
-        .expect("Must have a filename")
-        .to_string_lossy()
-        .to_string(),
-      source,
-    )?);
-
-    for component in components.rev().skip(1) {
+    let file = FileEntry::new(Self::file_name(destination), source)?;
+
+    self.upsert_file_entry(destination, file);
+
+    Ok(())
+  }
+
+  /// Like `upsert_file`, recording the file with `;version` rather than `;1`.
+  /// `version` must be within `1..=32767`.
+  pub fn upsert_file_versioned(
+    &mut self,
+    destination: impl AsRef<Path>,
+    source: impl AsRef<Path>,
+    version: u16,
+  ) -> Result<(), super::error::Error> {
+    if !(1..=32767).contains(&version) {
+      return Err(super::error::Error::InvalidFileVersion(version));
+    }
+
+    let destination = destination.as_ref();
+    let mut file = FileEntry::new(Self::file_name(destination), source)?;
+    file.version = Some(version);
+
+    self.upsert_file_entry(destination, file);
+
+    Ok(())
+  }
+
+  /// Stage every file under the host directory `source` at the same relative
+  /// path beneath `destination`, resolving clashes with staged files by
+  /// `policy`.
+  pub fn add_tree_with_policy(
+    &mut self,
+    source: impl AsRef<Path>,
+    destination: impl AsRef<Path>,
+    policy: MergePolicy,
+  ) -> Result<(), super::error::Error> {
+    let mut entries = std::fs::read_dir(source.as_ref())?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+      let source = entry.path();
+      let destination = destination.as_ref().join(entry.file_name());
+
+      if entry.file_type()?.is_dir() {
+        self.add_tree_with_policy(&source, &destination, policy)?;
+        continue;
+      }
+
+      if self.find_file_mut(&destination).is_some() {
+        match policy {
+          MergePolicy::Overwrite => {}
+          MergePolicy::Skip => continue,
+          MergePolicy::Error => {
+            return Err(super::error::Error::FileExists(
+              destination.to_string_lossy().to_string(),
+            ))
+          }
+        }
+      }
+
+      self.upsert_file(&destination, &source)?;
+    }
+
+    Ok(())
+  }
+
+  /// Stage a file whose content is streamed from `reader` when the image is
+  /// written. `reader` must yield exactly `length` bytes.
+  pub fn upsert_reader(
+    &mut self,
+    destination: impl AsRef<Path>,
+    reader: impl std::io::Read + 'static,
+    length: u64,
+  ) {
+    let destination = destination.as_ref();
+    let file = FileEntry::from_reader(Self::file_name(destination), reader, length);
+
+    self.upsert_file_entry(destination, file);
+  }
+
+  /// Attach an associated file, such as a resource fork, to the already
+  /// staged file at `destination`. Its record precedes the data file's and
+  /// shares its name.
+  pub fn upsert_associated_file(
+    &mut self,
+    destination: impl AsRef<Path>,
+    source: impl AsRef<Path>,
+  ) -> Result<(), super::error::Error> {
+    let destination = destination.as_ref();
+
+    let Some(data_file) = self.find_file_mut(destination) else {
+      return Err(super::error::Error::MissingDataFile(
+        destination.to_string_lossy().to_string(),
+      ));
+    };
+
+    // Recorded under the same identifier as the data file, version included.
+    let associated = FileEntry {
+      version: data_file.version,
+      omit_version: data_file.omit_version,
+      interchange_level: data_file.interchange_level,
+      ..FileEntry::new(data_file.name.clone(), source)?
+    };
+    data_file.associated = Some(Box::new(associated));
+
+    Ok(())
+  }
+
+  fn find_file_mut(&mut self, destination: &Path) -> Option<&mut FileEntry> {
+    let mut components = destination
+      .components()
+      .map(|c| c.as_os_str().to_string_lossy().to_string())
+      .collect::<Vec<_>>();
+    let file_name = components.pop()?;
+
+    let mut entries = &mut self.root.entries;
+
+    for component in components {
+      match entries.iter_mut().find(|e| e.name() == component)? {
+        Entry::Directory(dir) => entries = &mut dir.entries,
+        Entry::File(_) => return None,
+      }
+    }
+
+    match entries.iter_mut().find(|e| e.name() == file_name)? {
+      Entry::File(file) => Some(file),
+      Entry::Directory(_) => None,
+    }
+  }
+
+  fn file_name(destination: &Path) -> String {
+    // TODO(meowesque): Handle error more gracefully.
+    destination
+      .file_name()
+      .expect("Must have a filename")
+      .to_string_lossy()
+      .to_string()
+  }
+
+  fn upsert_file_entry(&mut self, destination: &Path, file: FileEntry) {
+    let mut tail = Entry::File(file);
+
+    for component in destination.components().rev().skip(1) {
       tail = Entry::Directory(DirectoryEntry {
         extent_lba: None,
         name: component.as_os_str().to_string_lossy().to_string(),
         entries: vec![tail],
+        data_length: None,
+        interchange_level: InterchangeLevel::Level1,
+        collision_suffix: None,
       });
     }
 
     self.root.upsert(tail);
-
-    Ok(())
   }
 }
@@ -1,20 +1,77 @@
-pub(crate) struct LbaAllocator {
+/// Hands out consecutive logical blocks.
+///
+/// Every address is a sector index, never a byte offset. Sizes in bytes are
+/// only accepted by `allocate_bytes`, which rounds up to whole sectors.
+pub struct LbaAllocator {
   sector_size: u32,
   next_lba: u32,
+  reserved: Vec<std::ops::Range<u32>>,
+  empty_lba: Option<u32>,
 }
 
 impl LbaAllocator {
-  pub(crate) fn new(sector_size: u32, start_lba: u32) -> Self {
+  /// `start_lba` is the sector index of the first allocation.
+  pub fn new(sector_size: u32, start_lba: u32) -> Self {
     Self {
       sector_size,
       next_lba: start_lba,
+      reserved: vec![],
+      empty_lba: None,
     }
   }
 
-  pub(crate) fn allocate(&mut self, size: u32) -> u32 {
+  /// Mark `range` as reserved, such as the system area and volume
+  /// descriptors. If it covers the next allocation, allocation resumes after
+  /// it; debug builds panic if a later allocation would overlap it.
+  pub fn reserve(&mut self, range: std::ops::Range<u32>) {
+    if range.contains(&self.next_lba) {
+      self.next_lba = range.end;
+    }
+
+    self.reserved.push(range);
+  }
+
+  pub fn sector_size(&self) -> u32 {
+    self.sector_size
+  }
+
+  /// The sector index the next allocation will start at.
+  pub fn next_lba(&self) -> u32 {
+    self.next_lba
+  }
+
+  /// Allocate `count` consecutive sectors, returning the first one.
+  pub fn allocate_sectors(&mut self, count: u32) -> u32 {
     let lba = self.next_lba;
-    let sectors = (size + self.sector_size - 1) / self.sector_size;
-    self.next_lba += sectors;
+    self.next_lba += count;
+
+    debug_assert!(
+      !self
+        .reserved
+        .iter()
+        .any(|r| count > 0 && r.start < self.next_lba && lba < r.end),
+      "sectors {lba}..{} overlap a reserved range",
+      self.next_lba
+    );
+
     lba
   }
-}
\ No newline at end of file
+
+  /// The sector every zero-length extent points at. It is allocated on
+  /// first use so empty extents never alias the allocation that follows.
+  pub fn allocate_empty(&mut self) -> u32 {
+    match self.empty_lba {
+      Some(lba) => lba,
+      None => {
+        let lba = self.allocate_sectors(1);
+        self.empty_lba = Some(lba);
+        lba
+      }
+    }
+  }
+
+  /// Allocate enough whole sectors to hold `size` bytes, returning the first one.
+  pub fn allocate_bytes(&mut self, size: u32) -> u32 {
+    self.allocate_sectors(size.div_ceil(self.sector_size))
+  }
+}
@@ -0,0 +1,50 @@
+//! Checksum manifests written alongside an image.
+//!
+//! Each file gets one line, `path\tsize\tsha256`, where `path` is the file's
+//! path on the disc, built from recorded identifiers, and `sha256` is the
+//! lowercase hex digest of its content.
+
+use sha2::{Digest, Sha256};
+
+/// Passes writes through to `inner` while hashing them.
+pub(crate) struct HashingWriter<W> {
+  inner: W,
+  hasher: Sha256,
+  written: u64,
+}
+
+impl<W> HashingWriter<W> {
+  pub(crate) fn new(inner: W) -> Self {
+    Self {
+      inner,
+      hasher: Sha256::new(),
+      written: 0,
+    }
+  }
+
+  /// Write the manifest line for everything written so far.
+  pub(crate) fn finish(self, manifest: &mut dyn std::io::Write, path: &str) -> std::io::Result<()> {
+    writeln!(
+      manifest,
+      "{}\t{}\t{:x}",
+      path,
+      self.written,
+      self.hasher.finalize()
+    )
+  }
+}
+
+impl<W: std::io::Write> std::io::Write for HashingWriter<W> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    let written = self.inner.write(buf)?;
+
+    self.hasher.update(&buf[..written]);
+    self.written += written as u64;
+
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    self.inner.flush()
+  }
+}
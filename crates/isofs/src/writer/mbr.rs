@@ -0,0 +1,39 @@
+/// Partition type recorded for the ISO partition, as used by isohybrid.
+const ISO_PARTITION_TYPE: u8 = 0x17;
+
+/// Offset of the first partition table entry.
+const PARTITION_TABLE: usize = 446;
+
+/// CHS address used for partitions addressed purely by LBA.
+const CHS_LBA_ONLY: [u8; 3] = [0xfe, 0xff, 0xff];
+
+/// A master boot record placed at the start of the system area so the image
+/// can also boot as a disk.
+pub(crate) struct MbrBoot {
+  pub(crate) template: [u8; 512],
+  /// Add a bootable partition table entry spanning the whole image.
+  pub(crate) partition_for_iso: bool,
+}
+
+impl MbrBoot {
+  /// The MBR to write for an image of `image_bytes` bytes.
+  pub(crate) fn sector(&self, image_bytes: u64) -> [u8; 512] {
+    let mut mbr = self.template;
+
+    if self.partition_for_iso {
+      let sectors = u32::try_from(image_bytes.div_ceil(512)).unwrap_or(u32::MAX);
+      let entry = &mut mbr[PARTITION_TABLE..PARTITION_TABLE + 16];
+
+      entry[0] = 0x80;
+      entry[1..4].copy_from_slice(&CHS_LBA_ONLY);
+      entry[4] = ISO_PARTITION_TYPE;
+      entry[5..8].copy_from_slice(&CHS_LBA_ONLY);
+      entry[8..12].copy_from_slice(&0u32.to_le_bytes());
+      entry[12..16].copy_from_slice(&sectors.to_le_bytes());
+    }
+
+    mbr[510] = 0x55;
+    mbr[511] = 0xaa;
+    mbr
+  }
+}
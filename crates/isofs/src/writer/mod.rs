@@ -1,12 +1,12 @@
 use crate::{
-  serialize::IsoSerialize,
-  spec::{self, VolumeDescriptorSetTerminator},
-  writer::volume::VolumeLike,
+  identifier::InterchangeLevel, serialize::IsoSerialize, spec, writer::volume::VolumeLike,
 };
 
 pub mod error;
 pub mod fs;
 pub mod lba;
+mod manifest;
+mod mbr;
 pub mod sector;
 pub mod volume;
 
@@ -22,136 +22,450 @@ impl Standard {
   }
 }
 
+/// An `Extension` the directory tree can be written with, building its
+/// identifiers from the ones the writer records for staged entries.
+pub trait WriterExtension: spec::Extension {
+  /// Identifier for an entry recorded as `identifier`, e.g. `FILE.TXT;1`
+  /// after sanitizing for the interchange level.
+  fn file_identifier(identifier: &str) -> Self::FileIdentifier;
+
+  /// Identifier of a directory's own (`.`) record.
+  fn current_directory() -> Self::FileIdentifier;
+
+  /// Identifier of a directory's parent (`..`) record.
+  fn parent_directory() -> Self::FileIdentifier;
+}
+
+impl WriterExtension for spec::NoExtension {
+  fn file_identifier(identifier: &str) -> Self::FileIdentifier {
+    spec::FileIdentifier::from_bytes_truncated(identifier.as_bytes())
+  }
+
+  fn current_directory() -> Self::FileIdentifier {
+    spec::FileIdentifier::current_directory()
+  }
+
+  fn parent_directory() -> Self::FileIdentifier {
+    spec::FileIdentifier::parent_directory()
+  }
+}
+
 pub struct WriterOptions {
   pub sector_size: u16,
   pub standard: Standard,
+  /// Zero-fill the image up to this many sectors. Images that are already
+  /// larger are left as is.
+  pub pad_to_sectors: Option<u32>,
+  /// Written to the application use area of every volume descriptor.
+  pub application_use: [u8; 512],
+  /// Generate a `TRANS.TBL` in every directory mapping each recorded
+  /// identifier back to its original name.
+  pub trans_tbl: bool,
+  /// Move directories nested deeper than the 8 levels ECMA-119 allows into
+  /// a root `RR_MOVED` directory, as described on
+  /// `fs::Filesystem::relocate_deep_directories`.
+  pub deep_relocation: bool,
+  /// Write file identifiers without the `;version` suffix. ECMA-119 requires
+  /// it, but some loaders reject it and most readers accept its absence.
+  pub omit_version: bool,
+  /// Interchange level file and directory identifiers are sanitized for,
+  /// exactly as `identifier::sanitize_identifier` and
+  /// `identifier::sanitize_directory_identifier` preview them. Siblings that
+  /// sanitize to the same identifier are told apart with a `_N` suffix.
+  pub interchange_level: InterchangeLevel,
+  /// Fail with `Error::InvalidIdentifier` for names that are not already
+  /// valid at `interchange_level`, as `identifier::validate_identifier` checks
+  /// them, instead of sanitizing them.
+  pub strict_identifiers: bool,
+}
+
+impl Default for WriterOptions {
+  fn default() -> Self {
+    Self {
+      sector_size: 2048,
+      standard: Standard::Iso9660,
+      pad_to_sectors: None,
+      application_use: [0; 512],
+      trans_tbl: false,
+      deep_relocation: false,
+      omit_version: false,
+      interchange_level: InterchangeLevel::Level1,
+      strict_identifiers: false,
+    }
+  }
 }
 
-pub struct IsoWriter {
+/// Writes images whose directory records are identified with `Ext`.
+pub struct IsoWriter<Ext = spec::NoExtension> {
   options: WriterOptions,
   volumes: Vec<volume::Volume>,
+  mbr: Option<mbr::MbrBoot>,
+  extension: std::marker::PhantomData<Ext>,
 }
 
 impl IsoWriter {
   pub fn new(options: WriterOptions) -> Self {
+    Self::with_extension(options)
+  }
+}
+
+impl<Ext: WriterExtension> IsoWriter<Ext> {
+  /// Like `new`, writing the directory tree with `Ext` identifiers.
+  pub fn with_extension(options: WriterOptions) -> Self {
     Self {
       options,
       volumes: vec![],
+      mbr: None,
+      extension: std::marker::PhantomData,
     }
   }
 
+  /// Write `mbr_template` into the system area so the image can also boot
+  /// from a disk. With `partition_for_iso`, its first partition table entry
+  /// is replaced by a bootable partition spanning the whole image.
+  pub fn set_mbr_boot(&mut self, mbr_template: [u8; 512], partition_for_iso: bool) {
+    self.mbr = Some(mbr::MbrBoot {
+      template: mbr_template,
+      partition_for_iso,
+    });
+  }
+
   pub fn add_volume(&mut self, volume: impl Into<volume::Volume>) {
     self.volumes.push(volume.into());
   }
 
-  pub fn write<W>(&mut self, mut writer: W) -> Result<(), error::Error>
+  /// Write the image. Files staged from the host are re-read on every call,
+  /// but `fs::FileContent::Reader` content can only be consumed once, so
+  /// writing again fails with `UnexpectedEof` for non-empty readers.
+  pub fn write<W>(&mut self, writer: W) -> Result<(), error::Error>
+  where
+    W: std::io::Write + std::io::Seek,
+  {
+    self.write_impl(writer, None)
+  }
+
+  /// Like `write`, additionally emitting a `path\tsize\tsha256` line to
+  /// `manifest` for every file as its content is written. `path` joins the
+  /// recorded identifiers, e.g. `DIR/FILE.TXT;1`, not the staged names.
+  /// Associated files are left out, since they share their data file's path,
+  /// and so are generated `TRANS.TBL` files.
+  pub fn write_with_manifest<W, M>(
+    &mut self,
+    writer: W,
+    mut manifest: M,
+  ) -> Result<(), error::Error>
+  where
+    W: std::io::Write + std::io::Seek,
+    M: std::io::Write,
+  {
+    self.write_impl(writer, Some(&mut manifest))?;
+    manifest.flush()?;
+    Ok(())
+  }
+
+  fn write_impl<W>(
+    &mut self,
+    mut writer: W,
+    manifest: Option<&mut dyn std::io::Write>,
+  ) -> Result<(), error::Error>
   where
     W: std::io::Write + std::io::Seek,
   {
+    struct WriteState<'a> {
+      sector_size: u64,
+      volume_sequence_number: u16,
+      trans_tbl: bool,
+      manifest: Option<&'a mut dyn std::io::Write>,
+    }
+
+    fn copy_content<W>(content: &fs::FileContent, writer: &mut W) -> Result<(), error::Error>
+    where
+      W: std::io::Write,
+    {
+      use std::io::Read;
+
+      let length = content.size();
+      let copied = match content {
+        fs::FileContent::Handle(handle, _) => {
+          use std::io::Seek;
+
+          // Rewind so that writing the same image twice copies the whole
+          // file both times.
+          let mut handle = handle;
+          handle.rewind()?;
+
+          let reader = std::io::BufReader::new(handle);
+          std::io::copy(&mut reader.take(length), writer)?
+        }
+        fs::FileContent::Reader(reader, _) => {
+          let mut reader = reader.borrow_mut();
+          std::io::copy(&mut (&mut *reader).take(length), writer)?
+        }
+      };
+
+      // The extent was sized from the staged length, so copying more would
+      // overwrite the next extent, and copying less would leave the directory
+      // record pointing past the end of the data.
+      if copied != length {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+      }
+
+      Ok(())
+    }
+
     fn write_file_entry<W>(
-      mut writer: &mut W,
+      writer: &mut W,
       file_entry: &fs::FileEntry,
-      sector_size: u64,
+      path: &str,
+      state: &mut WriteState,
     ) -> Result<(), error::Error>
     where
       W: std::io::Write + std::io::Seek,
     {
-      let mut reader = std::io::BufReader::new(&file_entry.handle);
+      if let Some(associated) = &file_entry.associated {
+        // Associated files share their data file's path, so only the data
+        // file is listed in the manifest.
+        let manifest = state.manifest.take();
+        let written = write_file_entry(&mut *writer, associated, path, state);
+        state.manifest = manifest;
+        written?;
+      }
 
       writer.seek(std::io::SeekFrom::Start(
-        file_entry.extent_lba.unwrap() as u64 * sector_size,
+        file_entry.extent_lba.unwrap() as u64 * state.sector_size,
       ))?;
-      std::io::copy(&mut reader, &mut *writer)?;
+
+      match state.manifest.as_deref_mut() {
+        Some(manifest) => {
+          let mut hashing_writer = manifest::HashingWriter::new(&mut *writer);
+          copy_content(&file_entry.content, &mut hashing_writer)?;
+          hashing_writer.finish(manifest, path)?;
+        }
+        None => copy_content(&file_entry.content, writer)?,
+      }
 
       Ok(())
     }
 
-    fn write_directory_entry<W, D>(
-      mut writer: &mut W,
+    fn write_directory_entry<Ext, W, D, P>(
+      writer: &mut W,
       directory_entry: &D,
-      sector_size: u64,
+      parent: &P,
+      path: &str,
+      state: &mut WriteState,
     ) -> Result<(), error::Error>
     where
+      Ext: WriterExtension,
       W: std::io::Write + std::io::Seek,
       D: fs::DirectoryLike + fs::EntryLike,
+      P: fs::EntryLike,
     {
       let mut sector_writer = sector::SectorWriter::new(
         &mut *writer,
         directory_entry.extent_lba().unwrap() as u64,
-        sector_size,
+        state.sector_size,
       );
 
-      // TODO(meowesque): Write . and .. entries.
+      let special_records = [
+        directory_entry
+          .descriptor::<Ext>()
+          .with_identifier(Ext::current_directory()),
+        parent
+          .descriptor::<Ext>()
+          .with_identifier(Ext::parent_directory()),
+      ];
 
       let mut byte_buf = vec![];
 
-      for entry in directory_entry.entries_iter() {
-        let entry_descriptor = entry.descriptor();
-
+      for mut entry_descriptor in special_records.into_iter().chain(
+        directory_entry
+          .entries_iter()
+          .flat_map(|e| e.descriptors::<Ext>()),
+      ) {
+        entry_descriptor.volume_sequence_number = state.volume_sequence_number;
         byte_buf.resize(entry_descriptor.extent(), 0);
         entry_descriptor.serialize(&mut byte_buf[..])?;
 
-        sector_writer.write_aligned(&byte_buf[..entry_descriptor.extent() as usize])?;
+        sector_writer.write_aligned(&byte_buf[..entry_descriptor.extent()])?;
       }
 
+      // The records must fill exactly the sectors allocated for them.
+      let (sector_ix, bytes_offset) = sector_writer.position();
+      let extent_lba = directory_entry.extent_lba().unwrap() as u64;
+      debug_assert_eq!(
+        (sector_ix - extent_lba + (bytes_offset > 0) as u64) * state.sector_size,
+        directory_entry.descriptor::<Ext>().data_length as u64,
+        "directory records disagree with their packed data length"
+      );
+
       for entry in directory_entry.entries_iter() {
-        write_entry(&mut *writer, entry, sector_size)?;
+        // Listed by recorded identifier, as a reader of the image finds it.
+        let entry_path = match path {
+          "" => entry.identifier(),
+          _ => format!("{}/{}", path, entry.identifier()),
+        };
+
+        // Generated translation tables are not staged content, so they are
+        // left out of the manifest.
+        if state.trans_tbl && entry.name() == fs::TRANS_TBL {
+          let manifest = state.manifest.take();
+          let written =
+            write_entry::<Ext, _, _>(&mut *writer, entry, directory_entry, &entry_path, state);
+          state.manifest = manifest;
+          written?;
+          continue;
+        }
+
+        write_entry::<Ext, _, _>(&mut *writer, entry, directory_entry, &entry_path, state)?;
       }
 
       Ok(())
     }
 
-    fn write_entry<W>(
-      mut writer: &mut W,
+    fn write_entry<Ext, W, P>(
+      writer: &mut W,
       entry: &fs::Entry,
-      sector_size: u64,
+      parent: &P,
+      path: &str,
+      state: &mut WriteState,
     ) -> Result<(), error::Error>
     where
+      Ext: WriterExtension,
       W: std::io::Write + std::io::Seek,
+      P: fs::EntryLike,
     {
       match entry {
-        fs::Entry::File(file_entry) => write_file_entry(&mut *writer, file_entry, sector_size),
+        fs::Entry::File(file_entry) => write_file_entry(&mut *writer, file_entry, path, state),
         fs::Entry::Directory(dir_entry) => {
-          write_directory_entry(&mut *writer, dir_entry, sector_size)
+          write_directory_entry::<Ext, _, _, _>(&mut *writer, dir_entry, parent, path, state)
+        }
+      }
+    }
+
+    for volume in self.volumes.iter() {
+      match volume {
+        volume::Volume::Primary(pv) => {
+          pv.validate_volume_set()?;
         }
       }
     }
 
-    let mut allocator = lba::LbaAllocator::new(
-      self.options.sector_size as u32,
-      /* System use */ 16 + self.volumes.len() as u32 + /* Set terminator */ 1,
-    );
+    let descriptor_area_end =
+      /* System use */ 16 + self.volumes.len() as u32 + /* Set terminator */ 1;
+
+    let mut allocator =
+      lba::LbaAllocator::new(self.options.sector_size as u32, descriptor_area_end);
+    allocator.reserve(0..descriptor_area_end);
+
+    for volume in self.volumes.iter_mut() {
+      match volume {
+        volume::Volume::Primary(pv) => {
+          // Before `RR_MOVED` and `TRANS.TBL` are generated, so only staged
+          // names are checked.
+          if self.options.strict_identifiers {
+            pv.filesystem
+              .validate_identifiers(self.options.interchange_level)?;
+          }
+
+          if self.options.deep_relocation {
+            pv.filesystem.relocate_deep_directories();
+          }
+
+          pv.filesystem
+            .set_interchange_level(self.options.interchange_level);
+
+          if self.options.omit_version {
+            pv.filesystem.omit_versions();
+          }
+
+          // After sanitizing and omitting versions, both of which can make
+          // distinct names collide.
+          pv.filesystem.make_identifiers_unique();
+
+          // Last, so the tables list the identifiers as written.
+          if self.options.trans_tbl {
+            pv.filesystem.upsert_trans_tbls(self.options.omit_version);
+          }
+
+          pv.filesystem.sort_entries();
+
+          // Once identifiers are final, as referenced files may be named by
+          // their recorded identifier.
+          pv.validate_referenced_files()?;
+        }
+      }
+    }
+
+    for volume in self.volumes.iter_mut() {
+      match volume {
+        volume::Volume::Primary(pv) => pv.filesystem.assign_extent_lbas::<Ext>(&mut allocator),
+      }
+    }
+
+    let volume_space_size = allocator
+      .next_lba()
+      .max(self.options.pad_to_sectors.unwrap_or(0));
 
     let context = volume::VolumeContext {
       sector_size: self.options.sector_size as u32,
       standard_identifier: self.options.standard.standard_identifier(),
+      volume_space_size,
+      application_use: self.options.application_use,
+    };
+
+    let mut state = WriteState {
+      sector_size: context.sector_size as u64,
+      volume_sequence_number: 1,
+      trans_tbl: self.options.trans_tbl,
+      manifest,
     };
 
     {
       let mut bytes: [u8; 2048] = [0; 2048];
+      let sector_size = state.sector_size;
 
-      writer.seek(std::io::SeekFrom::Start(
-        16 * 2048,
-      ))?;
+      for (ix, volume) in self.volumes.iter().enumerate() {
+        // Writing the directory tree moves the cursor, so seek back to this
+        // volume's descriptor sector.
+        writer.seek(std::io::SeekFrom::Start((16 + ix as u64) * sector_size))?;
 
-      for volume in self.volumes.iter_mut() {
         match volume {
           volume::Volume::Primary(pv) => {
-            pv.filesystem.assign_extent_lbas(&mut allocator);
+            state.volume_sequence_number = pv.volume_sequence_number;
             pv.descriptor(&context).serialize(&mut bytes)?;
             writer.write_all(&bytes)?;
-            write_directory_entry(&mut writer, &pv.filesystem.root, context.sector_size as u64)?;
+            write_directory_entry::<Ext, _, _, _>(
+              &mut writer,
+              &pv.filesystem.root,
+              &pv.filesystem.root,
+              "",
+              &mut state,
+            )?;
           }
         }
       }
 
       writer.seek(std::io::SeekFrom::Start(
-        (self.volumes.len() as u64 + 16) * self.options.sector_size as u64,
+        (16 + self.volumes.len() as u64) * sector_size,
       ))?;
 
       spec::VolumeDescriptorSetTerminator.serialize(&mut bytes)?;
 
       writer.write_all(&bytes)?;
+
+      // Extend the image to a whole number of sectors, including any
+      // requested padding, so its length matches the volume space size.
+      let image_size = volume_space_size as u64 * sector_size;
+
+      if writer.seek(std::io::SeekFrom::End(0))? < image_size {
+        writer.seek(std::io::SeekFrom::Start(image_size - 1))?;
+        writer.write_all(&[0])?;
+      }
+
+      if let Some(mbr) = &self.mbr {
+        writer.seek(std::io::SeekFrom::Start(0))?;
+        writer.write_all(&mbr.sector(image_size))?;
+      }
     }
 
     Ok(())
@@ -1,4 +1,36 @@
-pub(crate) struct SectorWriter<Storage> {
+/// Size in bytes of the whole sectors `write_aligned` fills when writing
+/// records of `record_lengths` in order, at least one sector.
+pub fn packed_size(record_lengths: impl IntoIterator<Item = u64>, sector_size: u64) -> u64 {
+  let mut sectors = 1;
+  let mut bytes_offset = 0;
+
+  for length in record_lengths {
+    let length = length.min(sector_size);
+
+    if bytes_offset + length > sector_size {
+      sectors += 1;
+      bytes_offset = 0;
+    }
+
+    bytes_offset += length;
+
+    if bytes_offset == sector_size {
+      sectors += 1;
+      bytes_offset = 0;
+    }
+  }
+
+  // A write that exactly filled the last sector doesn't occupy the next one.
+  if bytes_offset == 0 && sectors > 1 {
+    sectors -= 1;
+  }
+
+  sectors * sector_size
+}
+
+/// Writes records into consecutive sectors without letting any record
+/// straddle a sector boundary.
+pub struct SectorWriter<Storage> {
   storage: Storage,
   sector_ix: u64,
   sector_size: u64,
@@ -26,22 +58,32 @@ where
 
     // If we don't have enough space in the current sector to fit this buffer, move to the next one.
     if self.bytes_offset + buf.len() as u64 > self.sector_size {
-      self.sector_ix += 1;
-      self.bytes_offset = 0;
-
-      self
-        .storage
-        .seek(std::io::SeekFrom::Start(self.sector_ix * self.sector_size))?;
-    } else {
-      self.storage.seek(std::io::SeekFrom::Start(
-        self.sector_ix * self.sector_size + self.bytes_offset,
-      ))?;
+      self.next_sector();
     }
 
-    let written = self.storage.write(buf)?;
+    self.storage.seek(std::io::SeekFrom::Start(
+      self.sector_ix * self.sector_size + self.bytes_offset,
+    ))?;
+    self.storage.write_all(buf)?;
 
-    self.bytes_offset += written as u64;
+    self.bytes_offset += buf.len() as u64;
+
+    // A buffer that exactly fills the sector leaves no room, so the next
+    // write starts a fresh sector.
+    if self.bytes_offset == self.sector_size {
+      self.next_sector();
+    }
+
+    Ok(buf.len())
+  }
+
+  /// Sector index and byte offset the next write would start at, if it fits.
+  pub fn position(&self) -> (u64, u64) {
+    (self.sector_ix, self.bytes_offset)
+  }
 
-    Ok(written)
+  fn next_sector(&mut self) {
+    self.sector_ix += 1;
+    self.bytes_offset = 0;
   }
 }
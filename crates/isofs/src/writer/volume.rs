@@ -1,8 +1,11 @@
-use crate::{spec, writer::fs::EntryLike};
+use crate::spec;
 
 pub struct VolumeContext {
   pub sector_size: u32,
   pub standard_identifier: spec::StandardIdentifier,
+  /// Total number of sectors in the image.
+  pub volume_space_size: u32,
+  pub application_use: [u8; 512],
 }
 
 pub trait VolumeLike {
@@ -14,11 +17,191 @@ pub trait VolumeLike {
 
 pub struct PrimaryVolume {
   pub volume_id: String,
+  pub system_id: Option<String>,
   pub publisher: Option<String>,
   pub preparer: Option<String>,
+  pub application: Option<String>,
+  /// Staged name or recorded identifier of a file in the root directory
+  /// holding the copyright statement. The descriptor records the file's
+  /// identifier as written, whichever was given.
+  pub copyright_file: Option<String>,
+  /// As `copyright_file`, for a file holding the volume's abstract.
+  pub abstract_file: Option<String>,
+  /// As `copyright_file`, for a file holding bibliographic records.
+  pub bibliographic_file: Option<String>,
+  /// Number of volumes in the volume set, at least 1.
+  pub volume_set_size: u16,
+  /// This volume's position within the volume set, from 1 to
+  /// `volume_set_size`.
+  pub volume_sequence_number: u16,
+  /// Defaults to the time the image is written.
+  pub creation_date: Option<chrono::DateTime<chrono::Utc>>,
+  /// Defaults to the time the image is written.
+  pub modification_date: Option<chrono::DateTime<chrono::Utc>>,
+  /// Unspecified unless set.
+  pub expiration_date: Option<chrono::DateTime<chrono::Utc>>,
+  /// Unspecified unless set.
+  pub effective_date: Option<chrono::DateTime<chrono::Utc>>,
   pub filesystem: super::fs::Filesystem,
 }
 
+impl PrimaryVolume {
+  pub fn builder(volume_id: impl Into<String>) -> PrimaryVolumeBuilder {
+    PrimaryVolumeBuilder {
+      volume: PrimaryVolume {
+        volume_id: volume_id.into(),
+        system_id: None,
+        publisher: None,
+        preparer: None,
+        application: None,
+        copyright_file: None,
+        abstract_file: None,
+        bibliographic_file: None,
+        volume_set_size: 1,
+        volume_sequence_number: 1,
+        creation_date: None,
+        modification_date: None,
+        expiration_date: None,
+        effective_date: None,
+        filesystem: Default::default(),
+      },
+    }
+  }
+
+  /// Ensure the copyright, abstract and bibliographic file identifiers name
+  /// files that exist in the root directory, by either their staged name or
+  /// their recorded identifier.
+  pub(crate) fn validate_referenced_files(&self) -> Result<(), super::error::Error> {
+    let referenced = [
+      ("copyright file identifier", &self.copyright_file),
+      ("abstract file identifier", &self.abstract_file),
+      ("bibliographic file identifier", &self.bibliographic_file),
+    ];
+
+    for (field, name) in referenced {
+      let Some(name) = name else {
+        continue;
+      };
+
+      if self.filesystem.find_root_file(name).is_none() {
+        return Err(super::error::Error::MissingReferencedFile {
+          field,
+          name: name.clone(),
+        });
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Ensure the volume's position lies within a non-empty volume set.
+  pub(crate) fn validate_volume_set(&self) -> Result<(), super::error::Error> {
+    if self.volume_sequence_number == 0 || self.volume_sequence_number > self.volume_set_size {
+      return Err(super::error::Error::InvalidVolumeSequence {
+        volume_set_size: self.volume_set_size,
+        volume_sequence_number: self.volume_sequence_number,
+      });
+    }
+
+    Ok(())
+  }
+}
+
+/// The recorded identifier, version included unless omitted, of the root
+/// file `name` refers to, or filler if it names no file.
+fn referenced_file_identifier(
+  filesystem: &super::fs::Filesystem,
+  name: Option<&str>,
+) -> spec::DCharacters<37> {
+  let identifier = name.map(|name| {
+    filesystem
+      .find_root_file(name)
+      .map_or_else(|| name.to_string(), |file| file.identifier())
+  });
+
+  spec::DCharacters::from_bytes_truncated(identifier.unwrap_or_default().as_bytes())
+}
+
+pub struct PrimaryVolumeBuilder {
+  volume: PrimaryVolume,
+}
+
+impl PrimaryVolumeBuilder {
+  pub fn system_id(mut self, system_id: impl Into<String>) -> Self {
+    self.volume.system_id = Some(system_id.into());
+    self
+  }
+
+  pub fn publisher(mut self, publisher: impl Into<String>) -> Self {
+    self.volume.publisher = Some(publisher.into());
+    self
+  }
+
+  pub fn preparer(mut self, preparer: impl Into<String>) -> Self {
+    self.volume.preparer = Some(preparer.into());
+    self
+  }
+
+  pub fn application(mut self, application: impl Into<String>) -> Self {
+    self.volume.application = Some(application.into());
+    self
+  }
+
+  pub fn copyright_file(mut self, name: impl Into<String>) -> Self {
+    self.volume.copyright_file = Some(name.into());
+    self
+  }
+
+  pub fn abstract_file(mut self, name: impl Into<String>) -> Self {
+    self.volume.abstract_file = Some(name.into());
+    self
+  }
+
+  pub fn bibliographic_file(mut self, name: impl Into<String>) -> Self {
+    self.volume.bibliographic_file = Some(name.into());
+    self
+  }
+
+  pub fn volume_set_size(mut self, volume_set_size: u16) -> Self {
+    self.volume.volume_set_size = volume_set_size;
+    self
+  }
+
+  pub fn volume_sequence_number(mut self, volume_sequence_number: u16) -> Self {
+    self.volume.volume_sequence_number = volume_sequence_number;
+    self
+  }
+
+  pub fn creation_date<Tz: chrono::TimeZone>(mut self, date: chrono::DateTime<Tz>) -> Self {
+    self.volume.creation_date = Some(date.with_timezone(&chrono::Utc));
+    self
+  }
+
+  pub fn modification_date<Tz: chrono::TimeZone>(mut self, date: chrono::DateTime<Tz>) -> Self {
+    self.volume.modification_date = Some(date.with_timezone(&chrono::Utc));
+    self
+  }
+
+  pub fn expiration_date<Tz: chrono::TimeZone>(mut self, date: chrono::DateTime<Tz>) -> Self {
+    self.volume.expiration_date = Some(date.with_timezone(&chrono::Utc));
+    self
+  }
+
+  pub fn effective_date<Tz: chrono::TimeZone>(mut self, date: chrono::DateTime<Tz>) -> Self {
+    self.volume.effective_date = Some(date.with_timezone(&chrono::Utc));
+    self
+  }
+
+  pub fn filesystem(mut self, filesystem: super::fs::Filesystem) -> Self {
+    self.volume.filesystem = filesystem;
+    self
+  }
+
+  pub fn build(self) -> PrimaryVolume {
+    self.volume
+  }
+}
+
 impl VolumeLike for PrimaryVolume {
   type Descriptor = spec::PrimaryVolumeDescriptor;
 
@@ -30,38 +213,65 @@ impl VolumeLike for PrimaryVolume {
     spec::PrimaryVolumeDescriptor {
       standard_identifier: context.standard_identifier,
       version: spec::VolumeDescriptorVersion::Standard,
-      system_identifier: spec::ACharacters::from_bytes_truncated(b"LINUX"),
+      system_identifier: spec::ACharacters::from_bytes_truncated(
+        self.system_id.as_deref().unwrap_or("LINUX").as_bytes(),
+      ),
       volume_identifier: spec::DCharacters::from_bytes_truncated(self.volume_id().as_bytes()),
-      volume_space_size: 0,
-      volume_set_size: 0,
-      volume_sequence_number: 0,
+      volume_space_size: context.volume_space_size,
+      volume_set_size: self.volume_set_size,
+      volume_sequence_number: self.volume_sequence_number,
       logical_block_size: context.sector_size as u16,
       path_table_size: 0,
       type_l_path_table_location: self.filesystem.root.extent_lba.unwrap_or(0),
       optional_type_l_path_table_location: 0,
       type_m_path_table_location: 0,
       optional_type_m_path_table_location: 0,
-      root_directory_record: self.filesystem.root.root_descriptor(),
+      root_directory_record: spec::RootDirectoryRecord {
+        volume_sequence_number: self.volume_sequence_number,
+        ..self.filesystem.root.root_descriptor()
+      },
       volume_set_identifier: spec::DCharacters::from_bytes_truncated(b"abc"),
-      publisher_identifier: spec::ACharacters::from_bytes_truncated(b"hi noxie (:"),
-      data_preparer_identifier: spec::ACharacters::from_bytes_truncated(b"def"),
-      application_identifier: spec::ACharacters::from_bytes_truncated(b"ghi"),
-      copyright_file_identifier: spec::DCharacters::from_bytes_truncated(b"jkl"),
-      abstract_file_identifier: spec::DCharacters::from_bytes_truncated(b"mno"),
-      bibliographic_file_identifier: spec::DCharacters::from_bytes_truncated(b"pqr"),
-      creation_date: chrono::Utc::now().into(),
-      modification_date: chrono::Utc::now().into(),
-      expiration_date: chrono::Utc::now().into(),
-      effective_date: chrono::Utc::now().into(),
+      publisher_identifier: spec::ACharacters::from_bytes_truncated(
+        self.publisher.as_deref().unwrap_or_default().as_bytes(),
+      ),
+      data_preparer_identifier: spec::ACharacters::from_bytes_truncated(
+        self.preparer.as_deref().unwrap_or_default().as_bytes(),
+      ),
+      application_identifier: spec::ACharacters::from_bytes_truncated(
+        self.application.as_deref().unwrap_or_default().as_bytes(),
+      ),
+      copyright_file_identifier: referenced_file_identifier(
+        &self.filesystem,
+        self.copyright_file.as_deref(),
+      ),
+      abstract_file_identifier: referenced_file_identifier(
+        &self.filesystem,
+        self.abstract_file.as_deref(),
+      ),
+      bibliographic_file_identifier: referenced_file_identifier(
+        &self.filesystem,
+        self.bibliographic_file.as_deref(),
+      ),
+      creation_date: self.creation_date.unwrap_or_else(chrono::Utc::now).into(),
+      modification_date: self
+        .modification_date
+        .unwrap_or_else(chrono::Utc::now)
+        .into(),
+      expiration_date: self
+        .expiration_date
+        .map_or_else(spec::DigitsDate::unspecified, Into::into),
+      effective_date: self
+        .effective_date
+        .map_or_else(spec::DigitsDate::unspecified, Into::into),
       file_structure_version: spec::FileStructureVersion::Standard,
-      application_use: [0; 512],
+      application_use: context.application_use,
     }
   }
 }
 
-impl Into<Volume> for PrimaryVolume {
-  fn into(self) -> Volume {
-    Volume::Primary(self)
+impl From<PrimaryVolume> for Volume {
+  fn from(volume: PrimaryVolume) -> Self {
+    Volume::Primary(volume)
   }
 }
 
@@ -1,3 +1,2002 @@
-extern crate isofs;
+use isofs::{
+  parse::IsoParse,
+  serialize::IsoSerialize,
+  spec,
+  writer::{
+    error::Error,
+    fs::Filesystem,
+    lba::LbaAllocator,
+    volume::{PrimaryVolume, VolumeContext, VolumeLike},
+    IsoWriter, WriterOptions,
+  },
+};
 
-fn main() {}
+const SECTOR_SIZE: usize = 2048;
+
+fn try_write_volume_with(options: WriterOptions, volume: PrimaryVolume) -> Result<Vec<u8>, Error> {
+  let mut iso = IsoWriter::new(options);
+
+  iso.add_volume(volume);
+
+  let mut out = std::io::Cursor::new(Vec::new());
+  iso.write(&mut out)?;
+  Ok(out.into_inner())
+}
+
+fn try_write_volume(volume: PrimaryVolume) -> Result<Vec<u8>, Error> {
+  try_write_volume_with(WriterOptions::default(), volume)
+}
+
+fn write_iso_with(options: WriterOptions, filesystem: Filesystem) -> Vec<u8> {
+  try_write_volume_with(
+    options,
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  )
+  .unwrap()
+}
+
+fn write_iso(filesystem: Filesystem) -> Vec<u8> {
+  write_iso_with(WriterOptions::default(), filesystem)
+}
+
+fn u32_le(bytes: &[u8]) -> u32 {
+  u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+#[derive(Debug)]
+struct Record {
+  identifier: Vec<u8>,
+  extent_location: u32,
+  data_length: u32,
+  file_flags: u8,
+}
+
+/// Root directory `(extent_location, data_length)` from the PVD.
+fn root_extent(iso: &[u8]) -> (u32, u32) {
+  let record = &iso[16 * SECTOR_SIZE + 156..];
+  (u32_le(&record[2..]), u32_le(&record[10..]))
+}
+
+/// Decode the directory records of the extent at `lba`.
+fn directory_records(iso: &[u8], lba: u32, length: u32) -> Vec<Record> {
+  let start = lba as usize * SECTOR_SIZE;
+  let extent = &iso[start..start + length.next_multiple_of(SECTOR_SIZE as u32) as usize];
+  let mut records = vec![];
+
+  for sector in extent.chunks(SECTOR_SIZE) {
+    let mut offset = 0;
+
+    while offset < SECTOR_SIZE && sector[offset] != 0 {
+      let record = &sector[offset..offset + sector[offset] as usize];
+      let identifier_length = record[32] as usize;
+
+      records.push(Record {
+        identifier: record[33..33 + identifier_length].to_vec(),
+        extent_location: u32_le(&record[2..]),
+        data_length: u32_le(&record[10..]),
+        file_flags: record[25],
+      });
+
+      offset += record.len();
+    }
+  }
+
+  records
+}
+
+fn extent_bytes<'a>(iso: &'a [u8], record: &Record) -> &'a [u8] {
+  let start = record.extent_location as usize * SECTOR_SIZE;
+  &iso[start..start + record.data_length as usize]
+}
+
+#[test]
+fn reader_content_is_streamed_into_extent() {
+  let content = b"streamed from a reader".to_vec();
+  let mut filesystem = Filesystem::default();
+
+  filesystem.upsert_reader(
+    "stream.txt",
+    std::io::Cursor::new(content.clone()),
+    content.len() as u64,
+  );
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let records = directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(records.len(), 3);
+  assert_eq!(records[2].identifier, b"STREAM.TXT;1");
+  assert_eq!(extent_bytes(&iso, &records[2]), &content[..]);
+}
+
+#[test]
+fn lba_allocator_rounds_bytes_up_to_whole_sectors() {
+  let mut allocator = LbaAllocator::new(2048, 18);
+
+  assert_eq!(allocator.allocate_bytes(2049), 18);
+  assert_eq!(allocator.next_lba(), 20);
+  assert_eq!(allocator.allocate_bytes(0), 20);
+  assert_eq!(allocator.next_lba(), 20);
+}
+
+#[test]
+fn lba_allocator_start_is_a_sector_index() {
+  let mut allocator = LbaAllocator::new(2048, 18);
+
+  assert_eq!(allocator.allocate_sectors(3), 18);
+  assert_eq!(allocator.allocate_sectors(1), 21);
+}
+
+#[test]
+fn lba_allocator_skips_reserved_sectors() {
+  let mut allocator = LbaAllocator::new(2048, 18);
+  allocator.reserve(0..21);
+
+  assert!(allocator.allocate_sectors(1) >= 21);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "overlap a reserved range")]
+fn lba_allocator_panics_on_reserved_overlap() {
+  let mut allocator = LbaAllocator::new(2048, 18);
+  allocator.reserve(20..22);
+
+  allocator.allocate_sectors(3);
+}
+
+#[test]
+fn set_terminator_follows_volume_descriptors() {
+  let iso = write_iso(Filesystem::default());
+  let terminator = &iso[17 * SECTOR_SIZE..18 * SECTOR_SIZE];
+
+  assert_eq!(iso[16 * SECTOR_SIZE], 1);
+  assert_eq!(terminator[0], 255);
+  assert_eq!(&terminator[1..6], b"CD001");
+}
+
+fn serialize_pvd(volume: &PrimaryVolume) -> [u8; 2048] {
+  let context = VolumeContext {
+    sector_size: SECTOR_SIZE as u32,
+    standard_identifier: spec::StandardIdentifier::Cd001,
+    volume_space_size: 0,
+    application_use: [0; 512],
+  };
+  let mut bytes = [0; 2048];
+
+  volume.descriptor(&context).serialize(&mut bytes).unwrap();
+  bytes
+}
+
+#[test]
+fn primary_volume_builder_fills_descriptor_identifiers() {
+  let volume = PrimaryVolume::builder("VOLUME")
+    .system_id("SYSTEM")
+    .publisher("PUBLISHER")
+    .preparer("PREPARER")
+    .application("APPLICATION")
+    .build();
+  let pvd = serialize_pvd(&volume);
+
+  assert_eq!(&pvd[8..14], b"SYSTEM");
+  assert_eq!(&pvd[40..46], b"VOLUME");
+  assert_eq!(&pvd[318..327], b"PUBLISHER");
+  assert_eq!(&pvd[446..454], b"PREPARER");
+  assert_eq!(&pvd[574..585], b"APPLICATION");
+}
+
+fn reader_file(filesystem: &mut Filesystem, destination: &str, content: &[u8]) {
+  filesystem.upsert_reader(
+    destination,
+    std::io::Cursor::new(content.to_vec()),
+    content.len() as u64,
+  );
+}
+
+#[test]
+fn directories_start_with_current_and_parent_records() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "dir/file.txt", b"content");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(root.len(), 3);
+  assert_eq!(root[0].identifier, [0x00]);
+  assert_eq!(root[0].extent_location, root_lba);
+  assert_eq!(root[1].identifier, [0x01]);
+  assert_eq!(root[1].extent_location, root_lba);
+  assert_eq!(root[2].identifier, b"DIR");
+
+  let dir = directory_records(&iso, root[2].extent_location, root[2].data_length);
+
+  assert_eq!(dir.len(), 3);
+  assert_eq!(dir[0].identifier, [0x00]);
+  assert_eq!(dir[0].extent_location, root[2].extent_location);
+  assert_eq!(dir[1].identifier, [0x01]);
+  assert_eq!(dir[1].extent_location, root_lba);
+  assert_eq!(dir[2].identifier, b"FILE.TXT;1");
+  assert_eq!(extent_bytes(&iso, &dir[2]), b"content");
+}
+
+#[test]
+fn records_with_odd_and_even_identifiers_stay_aligned() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "odd.txt", b"odd");
+  reader_file(&mut filesystem, "even.txt", b"even");
+  reader_file(&mut filesystem, "last.txt", b"last");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let records = directory_records(&iso, root_lba, root_length);
+  let identifiers: Vec<_> = records[2..].iter().map(|r| &r.identifier[..]).collect();
+
+  assert_eq!(
+    identifiers,
+    [&b"EVEN.TXT;1"[..], &b"LAST.TXT;1"[..], &b"ODD.TXT;1"[..]]
+  );
+  assert_eq!(extent_bytes(&iso, &records[2]), b"even");
+}
+
+#[test]
+fn referenced_files_default_to_spaces() {
+  let pvd = serialize_pvd(&PrimaryVolume::builder("VOLUME").build());
+
+  assert_eq!(&pvd[702..813], &[b' '; 111][..]);
+}
+
+#[test]
+fn referenced_file_present_in_root_is_accepted() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "ABSTRACT.TXT", b"abstract");
+
+  let iso = try_write_volume(
+    PrimaryVolume::builder("VOLUME")
+      .abstract_file("ABSTRACT.TXT")
+      .filesystem(filesystem)
+      .build(),
+  )
+  .unwrap();
+  let pvd = &iso[16 * SECTOR_SIZE..17 * SECTOR_SIZE];
+
+  assert_eq!(&pvd[739..753], b"ABSTRACT.TXT;1");
+}
+
+#[test]
+fn referenced_files_record_their_written_identifier() {
+  for (omit_version, version) in [(false, ";1"), (true, "")] {
+    let mut filesystem = Filesystem::default();
+    reader_file(&mut filesystem, "copying.txt", b"copyright");
+    reader_file(&mut filesystem, "abstract.txt", b"abstract");
+
+    let iso = try_write_volume_with(
+      WriterOptions {
+        omit_version,
+        ..Default::default()
+      },
+      PrimaryVolume::builder("VOLUME")
+        .copyright_file("copying.txt")
+        .abstract_file("ABSTRACT.TXT")
+        .filesystem(filesystem)
+        .build(),
+    )
+    .unwrap();
+    let pvd = &iso[16 * SECTOR_SIZE..17 * SECTOR_SIZE];
+
+    assert_eq!(
+      &pvd[702..739],
+      format!("{:37}", format!("COPYING.TXT{version}")).as_bytes()
+    );
+    assert_eq!(
+      &pvd[739..776],
+      format!("{:37}", format!("ABSTRACT.TXT{version}")).as_bytes()
+    );
+  }
+}
+
+#[test]
+fn referenced_file_missing_from_root_is_rejected() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "DOCS/ABSTRACT.TXT", b"abstract");
+
+  let result = try_write_volume(
+    PrimaryVolume::builder("VOLUME")
+      .abstract_file("ABSTRACT.TXT")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  assert!(matches!(
+    result,
+    Err(Error::MissingReferencedFile { name, .. }) if name == "ABSTRACT.TXT"
+  ));
+}
+
+#[test]
+fn manifest_lists_every_file_with_its_sha256() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "abc.txt", b"abc");
+  reader_file(&mut filesystem, "dir/empty.txt", b"");
+
+  let mut iso = IsoWriter::new(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  let mut manifest = vec![];
+  iso
+    .write_with_manifest(std::io::Cursor::new(Vec::new()), &mut manifest)
+    .unwrap();
+
+  assert_eq!(
+    String::from_utf8(manifest).unwrap(),
+    "ABC.TXT;1\t3\tba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\n\
+     DIR/EMPTY.TXT;1\t0\te3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n"
+  );
+}
+
+#[test]
+fn manifest_leaves_out_associated_files() {
+  let dir = tempfile::tempdir().unwrap();
+  let resource = dir.path().join("resource");
+  std::fs::write(&resource, b"resource fork").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "abc.txt", b"abc");
+  filesystem
+    .upsert_associated_file("abc.txt", &resource)
+    .unwrap();
+
+  let mut iso = IsoWriter::new(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  let mut manifest = vec![];
+  iso
+    .write_with_manifest(std::io::Cursor::new(Vec::new()), &mut manifest)
+    .unwrap();
+
+  assert_eq!(
+    String::from_utf8(manifest).unwrap(),
+    "ABC.TXT;1\t3\tba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\n"
+  );
+}
+
+#[test]
+fn manifest_leaves_out_generated_trans_tbls() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "abc.txt", b"abc");
+  reader_file(&mut filesystem, "dir/empty.txt", b"");
+
+  let mut iso = IsoWriter::new(WriterOptions {
+    trans_tbl: true,
+    ..Default::default()
+  });
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  let mut manifest = vec![];
+  iso
+    .write_with_manifest(std::io::Cursor::new(Vec::new()), &mut manifest)
+    .unwrap();
+
+  assert_eq!(
+    String::from_utf8(manifest).unwrap(),
+    "ABC.TXT;1\t3\tba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad\n\
+     DIR/EMPTY.TXT;1\t0\te3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n"
+  );
+}
+
+fn sample_svd() -> spec::SupplementaryVolumeDescriptor {
+  let now = chrono::Utc::now();
+
+  spec::SupplementaryVolumeDescriptor {
+    standard_identifier: spec::StandardIdentifier::Cd001,
+    version: spec::VolumeDescriptorVersion::Standard,
+    volume_flags: spec::VolumeFlags::empty(),
+    system_identifier: spec::A1Characters::from_bytes_truncated(b"SYSTEM"),
+    volume_identifier: spec::D1Characters::from_bytes_truncated(b"VOLUME"),
+    volume_space_size: 42,
+    escape_sequences: spec::EscapeSequences::from_bytes_truncated(b"%/E"),
+    volume_set_size: 1,
+    volume_sequence_number: 1,
+    logical_block_size: SECTOR_SIZE as u16,
+    path_table_size: 10,
+    type_l_path_table_location: 20,
+    optional_type_l_path_table_location: 21,
+    type_m_path_table_location: 22,
+    optional_type_m_path_table_location: 23,
+    root_directory_record: spec::RootDirectoryRecord {
+      extent_location: 30,
+      data_length: SECTOR_SIZE as u32,
+      recording_date: now.into(),
+      file_flags: spec::FileFlags::DIRECTORY,
+      file_unit_size: 0,
+      interleave_gap_size: 0,
+      volume_sequence_number: 1,
+    },
+    volume_set_identifier: spec::D1Characters::from_bytes_truncated(b"SET"),
+    publisher_identifier: spec::A1Characters::from_bytes_truncated(b"PUBLISHER"),
+    data_preparer_identifier: spec::A1Characters::from_bytes_truncated(b"PREPARER"),
+    application_identifier: spec::A1Characters::from_bytes_truncated(b"APPLICATION"),
+    copyright_file_identifier: spec::D1Characters::from_bytes_truncated(b"COPYING"),
+    abstract_file_identifier: spec::D1Characters::from_bytes_truncated(b"ABSTRACT"),
+    bibliographic_file_identifier: spec::D1Characters::from_bytes_truncated(b"BIBLIO"),
+    creation_date: now.into(),
+    modification_date: now.into(),
+    expiration_date: now.into(),
+    effective_date: now.into(),
+    file_structure_version: spec::FileStructureVersion::Standard,
+    application_use: [0x5a; 512],
+  }
+}
+
+#[test]
+fn supplementary_volume_descriptor_round_trips() {
+  let mut original = [0; 2048];
+  sample_svd().serialize(&mut original).unwrap();
+
+  let parsed = spec::SupplementaryVolumeDescriptor::parse(&original).unwrap();
+  let mut reserialized = [0; 2048];
+  parsed.serialize(&mut reserialized).unwrap();
+
+  assert_eq!(&original[88..91], b"%/E");
+  assert_eq!(&original[148..156], [0, 0, 0, 22, 0, 0, 0, 23]);
+  assert_eq!(original, reserialized);
+}
+
+#[test]
+fn supplementary_volume_descriptor_rejects_other_types() {
+  let mut bytes = [0; 2048];
+  sample_svd().serialize(&mut bytes).unwrap();
+  bytes[0] = 1;
+
+  assert!(matches!(
+    spec::SupplementaryVolumeDescriptor::parse(&bytes),
+    Err(isofs::parse::IsoParseError::UnexpectedDescriptorType {
+      expected: 2,
+      got: 1,
+      ..
+    })
+  ));
+}
+
+fn record_with_flags(file_flags: spec::FileFlags) -> spec::DirectoryRecord<spec::NoExtension> {
+  spec::DirectoryRecord {
+    length: 0,
+    extended_attribute_length: 0,
+    extent_location: 0,
+    data_length: 0,
+    recording_date: chrono::Utc::now().into(),
+    file_flags,
+    file_unit_size: 0,
+    interleave_gap_size: 0,
+    volume_sequence_number: 1,
+    file_identifier_length: 0,
+    file_identifier: spec::FileIdentifier::from_bytes_truncated(b""),
+    system_use: vec![],
+  }
+  .with_identifier(spec::FileIdentifier::from_bytes_truncated(b"FILE.TXT;1"))
+}
+
+#[test]
+fn file_flag_predicates_match_their_bits() {
+  let plain = record_with_flags(spec::FileFlags::empty());
+  assert!(!plain.is_hidden());
+  assert!(!plain.is_directory());
+  assert!(!plain.is_associated());
+  assert!(!plain.is_record());
+  assert!(!plain.is_protected());
+  assert!(!plain.is_multi_extent());
+  assert!(plain.is_final_extent());
+
+  assert!(record_with_flags(spec::FileFlags::EXISTENCE).is_hidden());
+  assert!(record_with_flags(spec::FileFlags::DIRECTORY).is_directory());
+  assert!(record_with_flags(spec::FileFlags::ASSOCIATED_FILE).is_associated());
+  assert!(record_with_flags(spec::FileFlags::RECORD).is_record());
+  assert!(record_with_flags(spec::FileFlags::PROTECTION).is_protected());
+
+  let multi = record_with_flags(spec::FileFlags::MULTI_EXTENT);
+  assert!(multi.is_multi_extent());
+  assert!(!multi.is_final_extent());
+}
+
+#[test]
+fn pad_to_sectors_zero_fills_the_image() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "TINY.TXT", b"tiny");
+
+  let iso = write_iso_with(
+    WriterOptions {
+      pad_to_sectors: Some(512),
+      ..Default::default()
+    },
+    filesystem,
+  );
+
+  assert_eq!(iso.len(), 512 * SECTOR_SIZE);
+  assert_eq!(u32_le(&iso[16 * SECTOR_SIZE + 80..]), 512);
+}
+
+#[test]
+fn primary_volume_descriptor_round_trips() {
+  let original = serialize_pvd(&PrimaryVolume::builder("VOLUME").build());
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&original).unwrap();
+  let mut reserialized = [0; 2048];
+  parsed.serialize(&mut reserialized).unwrap();
+
+  assert_eq!(original, reserialized);
+}
+
+#[test]
+fn short_descriptor_input_is_an_error() {
+  for input in [&[][..], &[1][..], &[0; 2047][..]] {
+    assert!(matches!(
+      spec::PrimaryVolumeDescriptor::parse(input),
+      Err(isofs::parse::IsoParseError::InputTooSmall { .. })
+    ));
+    assert!(matches!(
+      spec::SupplementaryVolumeDescriptor::parse(input),
+      Err(isofs::parse::IsoParseError::InputTooSmall { .. })
+    ));
+  }
+}
+
+#[test]
+fn malformed_dates_are_an_error() {
+  let mut bytes = serialize_pvd(&PrimaryVolume::builder("VOLUME").build());
+  bytes[813] = b'X';
+
+  assert!(matches!(
+    spec::PrimaryVolumeDescriptor::parse(&bytes),
+    Err(isofs::parse::IsoParseError::InvalidDigits { .. })
+  ));
+}
+
+#[test]
+fn all_zero_dates_parse_as_unspecified() {
+  let mut bytes = serialize_pvd(&PrimaryVolume::builder("VOLUME").build());
+  bytes[847..864].fill(0);
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&bytes).unwrap();
+  assert!(parsed.expiration_date.is_unspecified());
+}
+
+#[test]
+fn volume_set_position_is_recorded() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "DISC2.TXT", b"two");
+
+  let iso = try_write_volume(
+    PrimaryVolume::builder("TEST")
+      .volume_set_size(3)
+      .volume_sequence_number(2)
+      .filesystem(filesystem)
+      .build(),
+  )
+  .unwrap();
+
+  let pvd = spec::PrimaryVolumeDescriptor::parse(&iso[16 * SECTOR_SIZE..]).unwrap();
+  assert_eq!(pvd.volume_set_size, 3);
+  assert_eq!(pvd.volume_sequence_number, 2);
+  assert_eq!(pvd.root_directory_record.volume_sequence_number, 2);
+
+  let (lba, length) = root_extent(&iso);
+  let start = lba as usize * SECTOR_SIZE;
+  let mut offset = 0;
+
+  while offset < length as usize && iso[start + offset] != 0 {
+    let record = &iso[start + offset..];
+    assert_eq!(u16::from_le_bytes([record[28], record[29]]), 2);
+    offset += record[0] as usize;
+  }
+
+  // The remainder of the packed extent is padding.
+  assert!(iso[start + offset..start + length as usize]
+    .iter()
+    .all(|&b| b == 0));
+}
+
+#[test]
+fn volume_sequence_outside_the_set_is_rejected() {
+  for (set_size, sequence) in [(0, 0), (1, 0), (2, 3)] {
+    let result = try_write_volume(
+      PrimaryVolume::builder("TEST")
+        .volume_set_size(set_size)
+        .volume_sequence_number(sequence)
+        .build(),
+    );
+
+    assert!(matches!(result, Err(Error::InvalidVolumeSequence { .. })));
+  }
+}
+
+#[test]
+fn identifiers_are_space_padded_and_trimmed_on_read() {
+  let pvd = serialize_pvd(&PrimaryVolume::builder("SHORT").build());
+
+  assert_eq!(&pvd[40..45], b"SHORT");
+  assert!(pvd[45..72].iter().all(|&b| b == b' '));
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&pvd).unwrap();
+  assert_eq!(parsed.volume_identifier.as_trimmed_str(), "SHORT");
+  assert_eq!(parsed.publisher_identifier.as_trimmed_str(), "");
+}
+
+#[test]
+fn create_writes_files_into_the_root() {
+  let dir = tempfile::tempdir().unwrap();
+  let hello = dir.path().join("hello.txt");
+  let world = dir.path().join("world.txt");
+  std::fs::write(&hello, b"hello").unwrap();
+  std::fs::write(&world, b"world!").unwrap();
+
+  let output = dir.path().join("out.iso");
+  isofs::create(
+    &output,
+    "CREATED",
+    &[("HELLO.TXT", &hello), ("WORLD.TXT", &world)],
+  )
+  .unwrap();
+
+  let iso = std::fs::read(&output).unwrap();
+  let (lba, length) = root_extent(&iso);
+  let records = directory_records(&iso, lba, length);
+
+  assert_eq!(
+    &iso[16 * SECTOR_SIZE + 40..16 * SECTOR_SIZE + 47],
+    b"CREATED"
+  );
+  assert_eq!(records[2].identifier, b"HELLO.TXT;1");
+  assert_eq!(extent_bytes(&iso, &records[2]), b"hello");
+  assert_eq!(records[3].identifier, b"WORLD.TXT;1");
+  assert_eq!(extent_bytes(&iso, &records[3]), b"world!");
+}
+
+#[test]
+fn out_of_range_date_fields_are_rejected() {
+  let date: spec::DigitsDate = chrono::NaiveDate::from_ymd_opt(10000, 1, 1)
+    .unwrap()
+    .and_hms_opt(0, 0, 0)
+    .unwrap()
+    .and_utc()
+    .into();
+  let mut bytes = [0; 17];
+
+  assert!(matches!(
+    date.serialize(&mut bytes),
+    Err(isofs::serialize::IsoSerializeError::FieldOutOfRange {
+      field: "DigitsYear",
+      value: 10000,
+      ..
+    })
+  ));
+}
+
+#[test]
+fn identifier_parts_split_on_separators() {
+  use isofs::identifier::IdentifierParts;
+
+  let cases = [
+    ("FILE.TXT;2", "FILE", Some("TXT"), Some(2)),
+    ("NOEXT;1", "NOEXT", None, Some(1)),
+    ("NOEXT.;1", "NOEXT", Some(""), Some(1)),
+    ("DIR", "DIR", None, None),
+  ];
+
+  for (identifier, name, extension, version) in cases {
+    let parts = IdentifierParts::parse(identifier);
+
+    assert_eq!(
+      parts,
+      IdentifierParts {
+        name,
+        extension,
+        version
+      }
+    );
+    assert_eq!(parts.to_identifier(), identifier);
+  }
+}
+
+#[test]
+fn sanitized_identifiers_preview_the_written_name() {
+  use isofs::identifier::{sanitize_identifier, InterchangeLevel};
+
+  assert_eq!(
+    sanitize_identifier("My File.TXT", InterchangeLevel::Level2),
+    "MY_FILE.TXT;1"
+  );
+  assert_eq!(
+    sanitize_identifier("My File.TXT", InterchangeLevel::Level1),
+    "MY_FILE.TXT;1"
+  );
+  assert_eq!(
+    sanitize_identifier("A Long File Name.text", InterchangeLevel::Level1),
+    "A_LONG_F.TEX;1"
+  );
+  assert_eq!(
+    sanitize_identifier("A Long File Name.text", InterchangeLevel::Level2),
+    "A_LONG_FILE_NAME.TEXT;1"
+  );
+  assert_eq!(
+    sanitize_identifier("readme", InterchangeLevel::Level1),
+    "README.;1"
+  );
+}
+
+#[test]
+fn written_identifiers_match_their_preview() {
+  use isofs::identifier::{sanitize_identifier, InterchangeLevel};
+
+  for level in [InterchangeLevel::Level1, InterchangeLevel::Level2] {
+    let mut filesystem = Filesystem::default();
+    reader_file(&mut filesystem, "my long file.text", b"content");
+
+    let iso = write_iso_with(
+      WriterOptions {
+        interchange_level: level,
+        ..Default::default()
+      },
+      filesystem,
+    );
+    let (root_lba, root_length) = root_extent(&iso);
+    let root = directory_records(&iso, root_lba, root_length);
+
+    assert_eq!(
+      root[2].identifier,
+      sanitize_identifier("my long file.text", level).as_bytes()
+    );
+  }
+}
+
+#[test]
+fn associated_file_record_precedes_its_data_file() {
+  let dir = tempfile::tempdir().unwrap();
+  let data = dir.path().join("data");
+  let resource = dir.path().join("resource");
+  std::fs::write(&data, b"data fork").unwrap();
+  std::fs::write(&resource, b"resource fork").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_file("APP/ICON.RSR", &data).unwrap();
+  filesystem
+    .upsert_associated_file("APP/ICON.RSR", &resource)
+    .unwrap();
+
+  let iso = write_iso(filesystem);
+  let (lba, length) = root_extent(&iso);
+  let app = &directory_records(&iso, lba, length)[2];
+  let records = directory_records(&iso, app.extent_location, app.data_length);
+
+  assert_eq!(records.len(), 4);
+  assert_eq!(records[2].identifier, b"ICON.RSR;1");
+  assert_eq!(records[3].identifier, b"ICON.RSR;1");
+  assert_eq!(
+    records[2].file_flags,
+    spec::FileFlags::ASSOCIATED_FILE.bits()
+  );
+  assert_eq!(records[3].file_flags, 0);
+  assert_eq!(extent_bytes(&iso, &records[2]), b"resource fork");
+  assert_eq!(extent_bytes(&iso, &records[3]), b"data fork");
+}
+
+#[test]
+fn associated_file_shares_the_data_file_version() {
+  let dir = tempfile::tempdir().unwrap();
+  let data = dir.path().join("data");
+  let resource = dir.path().join("resource");
+  std::fs::write(&data, b"data fork").unwrap();
+  std::fs::write(&resource, b"resource fork").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem
+    .upsert_file_versioned("ICON.RSR", &data, 3)
+    .unwrap();
+  filesystem
+    .upsert_associated_file("ICON.RSR", &resource)
+    .unwrap();
+
+  let iso = write_iso(filesystem);
+  let (lba, length) = root_extent(&iso);
+  let records = directory_records(&iso, lba, length);
+
+  assert_eq!(records[2].identifier, b"ICON.RSR;3");
+  assert_eq!(records[3].identifier, b"ICON.RSR;3");
+  assert_eq!(extent_bytes(&iso, &records[2]), b"resource fork");
+}
+
+#[test]
+fn associated_file_requires_a_data_file() {
+  let mut filesystem = Filesystem::default();
+
+  assert!(matches!(
+    filesystem.upsert_associated_file("MISSING.TXT", "Cargo.toml"),
+    Err(Error::MissingDataFile(_))
+  ));
+}
+
+#[test]
+fn only_file_identifiers_carry_a_version() {
+  use isofs::identifier::{sanitize_directory_identifier, InterchangeLevel};
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "data/data.txt", b"data");
+
+  let iso = write_iso(filesystem);
+  let (lba, length) = root_extent(&iso);
+  let data = &directory_records(&iso, lba, length)[2];
+  let records = directory_records(&iso, data.extent_location, data.data_length);
+
+  assert_eq!(data.identifier, b"DATA");
+  assert_eq!(
+    data.identifier,
+    sanitize_directory_identifier("data", InterchangeLevel::Level1).as_bytes()
+  );
+  assert_eq!(records[2].identifier, b"DATA.TXT;1");
+}
+
+#[test]
+fn identifiers_display_as_text() {
+  let identifier = spec::DCharacters::<32>::from_bytes_truncated(b"HELLO");
+
+  assert_eq!(identifier.to_string(), "HELLO");
+  assert_eq!(format!("{identifier:?}"), "DCharacters(\"HELLO\")");
+  assert_eq!(
+    spec::FileIdentifier::<32>::from_bytes_truncated(b"FILE.TXT;1").to_string(),
+    "FILE.TXT;1"
+  );
+  assert_eq!(
+    spec::FileIdentifier::<32>::current_directory().to_string(),
+    "."
+  );
+  assert_eq!(
+    spec::FileIdentifier::<32>::parent_directory().to_string(),
+    ".."
+  );
+}
+
+#[test]
+fn identifier_violations_name_the_broken_rule() {
+  use isofs::identifier::{validate_identifier, IdentifierViolation, InterchangeLevel};
+
+  assert_eq!(
+    validate_identifier("FILE.TXT;1", InterchangeLevel::Level1),
+    Ok(())
+  );
+  assert_eq!(
+    validate_identifier("LONGFILENAME.TXT", InterchangeLevel::Level1),
+    Err(IdentifierViolation::TooLong)
+  );
+  assert_eq!(
+    validate_identifier("LONGFILENAME.TXT", InterchangeLevel::Level2),
+    Ok(())
+  );
+  assert_eq!(
+    validate_identifier("my file.txt", InterchangeLevel::Level2),
+    Err(IdentifierViolation::IllegalCharacter('m'))
+  );
+  assert_eq!(
+    validate_identifier("ARCHIVE.TAR.GZ", InterchangeLevel::Level2),
+    Err(IdentifierViolation::TooManyDots)
+  );
+  assert_eq!(
+    validate_identifier(".;1", InterchangeLevel::Level2),
+    Err(IdentifierViolation::EmptyName)
+  );
+  assert_eq!(
+    validate_identifier("FILE.TXT;32767", InterchangeLevel::Level1),
+    Ok(())
+  );
+  assert_eq!(
+    validate_identifier("FILE.TXT;0", InterchangeLevel::Level1),
+    Err(IdentifierViolation::InvalidVersion(0))
+  );
+  assert_eq!(
+    validate_identifier("FILE.TXT;32768", InterchangeLevel::Level1),
+    Err(IdentifierViolation::InvalidVersion(32768))
+  );
+}
+
+#[test]
+fn strict_identifiers_reject_names_that_need_sanitizing() {
+  use isofs::identifier::{IdentifierViolation, InterchangeLevel};
+
+  let strict = || WriterOptions {
+    strict_identifiers: true,
+    ..Default::default()
+  };
+  let write = |options: WriterOptions, filesystem: Filesystem| {
+    try_write_volume_with(
+      options,
+      PrimaryVolume::builder("TEST")
+        .filesystem(filesystem)
+        .build(),
+    )
+  };
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "DIR/FILE.TXT", b"content");
+  assert!(write(strict(), filesystem).is_ok());
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "file.txt", b"content");
+  assert!(matches!(
+    write(strict(), filesystem),
+    Err(Error::InvalidIdentifier {
+      name,
+      violation: IdentifierViolation::IllegalCharacter('f'),
+    }) if name == "file.txt"
+  ));
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "LONGDIRECTORY/FILE.TXT", b"content");
+  assert!(matches!(
+    write(strict(), filesystem),
+    Err(Error::InvalidIdentifier {
+      name,
+      violation: IdentifierViolation::TooLong,
+    }) if name == "LONGDIRECTORY"
+  ));
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "LONGDIRECTORY/FILE.TXT", b"content");
+  assert!(write(
+    WriterOptions {
+      interchange_level: InterchangeLevel::Level2,
+      ..strict()
+    },
+    filesystem
+  )
+  .is_ok());
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "file.txt", b"content");
+  assert!(write(WriterOptions::default(), filesystem).is_ok());
+}
+
+#[test]
+fn volume_dates_can_be_set_or_left_unspecified() {
+  use chrono::TimeZone;
+
+  let expiration = chrono::Utc
+    .with_ymd_and_hms(2099, 12, 31, 23, 59, 58)
+    .unwrap();
+  let pvd = serialize_pvd(
+    &PrimaryVolume::builder("DATED")
+      .expiration_date(expiration)
+      .build(),
+  );
+
+  assert_eq!(&pvd[847..863], b"2099123123595800");
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&pvd).unwrap();
+  assert!(!parsed.creation_date.is_unspecified());
+  assert!(!parsed.expiration_date.is_unspecified());
+  assert!(parsed.effective_date.is_unspecified());
+  assert_eq!(&pvd[864..881], b"0000000000000000\0");
+}
+
+#[test]
+fn hybrid_mbr_partition_spans_the_image() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "BOOT.TXT", b"boot");
+
+  let mut iso = IsoWriter::new(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  let mut template = [0; 512];
+  template[0] = 0xeb;
+  iso.set_mbr_boot(template, true);
+
+  let mut out = std::io::Cursor::new(Vec::new());
+  iso.write(&mut out).unwrap();
+  let iso = out.into_inner();
+
+  let entry = &iso[446..462];
+  assert_eq!(iso[0], 0xeb);
+  assert_eq!(&iso[510..512], [0x55, 0xaa]);
+  assert_eq!(entry[0], 0x80);
+  assert_eq!(u32_le(&entry[8..]), 0);
+  assert_eq!(u32_le(&entry[12..]) as usize * 512, iso.len());
+  assert_eq!(&iso[16 * SECTOR_SIZE + 1..16 * SECTOR_SIZE + 6], b"CD001");
+}
+
+#[test]
+fn entry_metadata_reports_the_staged_file() {
+  use isofs::writer::fs::DirectoryLike;
+
+  let dir = tempfile::tempdir().unwrap();
+  let source = dir.path().join("source.bin");
+  std::fs::write(&source, [7; 3000]).unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_file("DIR/FILE.BIN", &source).unwrap();
+
+  let directory = filesystem.root.find("DIR").unwrap();
+  assert!(directory.metadata().is_dir);
+
+  let isofs::writer::fs::Entry::Directory(directory) = directory else {
+    panic!("DIR is not a directory");
+  };
+  let metadata = directory.find("FILE.BIN").unwrap().metadata();
+
+  assert!(!metadata.is_dir);
+  assert_eq!(metadata.size, std::fs::metadata(&source).unwrap().len());
+  assert_eq!(metadata.source, Some(source.as_path()));
+  assert!(metadata.modified.is_some());
+}
+
+#[test]
+fn directory_records_round_trip() {
+  for identifier in [&b"ODD.TXT;1"[..], &b"EVEN.TXT;1"[..], &[0x00][..]] {
+    let record = record_with_flags(spec::FileFlags::DIRECTORY)
+      .with_identifier(spec::FileIdentifier::from_bytes_truncated(identifier));
+    let mut original = vec![0; record.extent()];
+    record.serialize(&mut original).unwrap();
+
+    let parsed = spec::DirectoryRecord::<spec::NoExtension>::parse(&original).unwrap();
+    let mut reserialized = vec![0; parsed.extent()];
+    parsed.serialize(&mut reserialized).unwrap();
+
+    assert_eq!(original.len(), original[0] as usize);
+    assert_eq!(original, reserialized);
+  }
+}
+
+#[test]
+fn directory_records_keep_their_system_use_area() {
+  let mut record = record_with_flags(spec::FileFlags::empty());
+  record.system_use = b"RR\x05\x01\x81".to_vec();
+  let record = record.with_identifier(spec::FileIdentifier::from_bytes_truncated(b"ODD.TXT;1"));
+  let mut original = vec![0; record.extent()];
+  record.serialize(&mut original).unwrap();
+
+  let parsed = spec::DirectoryRecord::<spec::NoExtension>::parse(&original).unwrap();
+  let mut reserialized = vec![0; parsed.extent()];
+  parsed.serialize(&mut reserialized).unwrap();
+
+  assert_eq!(original[0], 47);
+  assert_eq!(&original[42..], b"RR\x05\x01\x81");
+  assert_eq!(parsed.system_use, b"RR\x05\x01\x81");
+  assert_eq!(original, reserialized);
+}
+
+#[test]
+fn directory_record_identifiers_too_long_to_hold_are_rejected() {
+  let mut bytes = vec![0; 33 + 40 + 1];
+  bytes[0] = bytes.len() as u8;
+  bytes[32] = 40;
+  bytes[33..73].fill(b'A');
+
+  assert!(matches!(
+    spec::DirectoryRecord::<spec::NoExtension>::parse(&bytes),
+    Err(isofs::parse::IsoParseError::IdentifierTooLong {
+      length: 40,
+      capacity: 37,
+      ..
+    })
+  ));
+}
+
+#[test]
+fn root_directory_record_identifies_itself() {
+  let pvd = serialize_pvd(&PrimaryVolume::builder("ROOT").build());
+
+  assert_eq!(pvd[156], 34);
+  assert_eq!(&pvd[156 + 32..156 + 34], [1, 0]);
+}
+
+#[test]
+fn empty_identifier_fields_hold_filler_and_reserved_fields_zero() {
+  let mut svd = sample_svd();
+  svd.copyright_file_identifier = spec::D1Characters::from_bytes_truncated(b"");
+  let mut bytes = [0xff; 2048];
+  svd.serialize(&mut bytes).unwrap();
+
+  assert_eq!(bytes[702..739], [spec::FILLER; 37]);
+  assert_eq!(&bytes[22..40], [spec::FILLER; 18]);
+  assert_eq!(bytes[72..80], [spec::RESERVED; 8]);
+  assert_eq!(bytes[882], spec::RESERVED);
+  assert!(bytes[1395..].iter().all(|&b| b == spec::RESERVED));
+}
+
+#[test]
+fn patching_a_field_leaves_other_bytes_untouched() {
+  use spec::{PrimaryVolumeDescriptor, PvdField};
+
+  let original = serialize_pvd(&PrimaryVolume::builder("BEFORE").build());
+  let mut patched = original;
+  PrimaryVolumeDescriptor::patch_field(&mut patched, PvdField::VolumeIdentifier, b"AFTER").unwrap();
+
+  let range = PvdField::VolumeIdentifier.range();
+  assert_eq!(range, 40..72);
+  assert_eq!(&patched[40..45], b"AFTER");
+  assert!(patched[45..72].iter().all(|&b| b == spec::FILLER));
+  assert_eq!(original[..40], patched[..40]);
+  assert_eq!(original[72..], patched[72..]);
+
+  assert!(matches!(
+    PrimaryVolumeDescriptor::patch_field(&mut patched, PvdField::VolumeSpaceSize, &[1, 2]),
+    Err(isofs::serialize::IsoSerializeError::FieldLengthMismatch { .. })
+  ));
+}
+
+#[test]
+fn application_use_is_written_to_the_descriptor() {
+  let mut application_use = [0; 512];
+  application_use[..6].copy_from_slice(b"isopod");
+  application_use[511] = 0xff;
+
+  let mut iso = IsoWriter::new(WriterOptions {
+    application_use,
+    ..Default::default()
+  });
+  iso.add_volume(PrimaryVolume::builder("TEST").build());
+
+  let mut out = std::io::Cursor::new(Vec::new());
+  iso.write(&mut out).unwrap();
+  let iso = out.into_inner();
+
+  let pvd = spec::PrimaryVolumeDescriptor::parse(&iso[16 * SECTOR_SIZE..]).unwrap();
+  assert_eq!(pvd.application_use, application_use);
+}
+
+#[test]
+fn sector_writer_rolls_over_after_an_exactly_full_sector() {
+  use isofs::writer::sector::SectorWriter;
+
+  let mut out = std::io::Cursor::new(Vec::new());
+  let mut writer = SectorWriter::new(&mut out, 1, 64);
+
+  writer.write_aligned(&[1; 40]).unwrap();
+  writer.write_aligned(&[2; 24]).unwrap();
+  assert_eq!(writer.position(), (2, 0));
+
+  writer.write_aligned(&[3; 10]).unwrap();
+  writer.write_aligned(&[4; 60]).unwrap();
+  assert_eq!(writer.position(), (3, 60));
+
+  let out = out.into_inner();
+  assert_eq!(out[64..104], [1; 40]);
+  assert_eq!(out[104..128], [2; 24]);
+  assert_eq!(out[128..138], [3; 10]);
+  assert_eq!(out[192..252], [4; 60]);
+}
+
+#[test]
+fn trans_tbl_lists_long_names_in_each_directory() {
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_reader("readme.md", std::io::empty(), 0);
+  filesystem.upsert_reader("documentation/long_file_name.txt", std::io::empty(), 0);
+
+  let iso = write_iso_with(
+    WriterOptions {
+      trans_tbl: true,
+      ..Default::default()
+    },
+    filesystem,
+  );
+
+  let find = |records: &[Record], identifier: &[u8]| {
+    records
+      .iter()
+      .position(|r| r.identifier == identifier)
+      .unwrap()
+  };
+
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let root_tbl = &root[find(&root, b"TRANS.TBL;1")];
+
+  assert_eq!(
+    extent_bytes(&iso, root_tbl),
+    b"F README.MD;1 readme.md\nD DOCUMENT documentation\n"
+  );
+
+  let docs = &root[find(&root, b"DOCUMENT")];
+  let docs = directory_records(&iso, docs.extent_location, docs.data_length);
+  let docs_tbl = &docs[find(&docs, b"TRANS.TBL;1")];
+
+  assert_eq!(
+    extent_bytes(&iso, docs_tbl),
+    b"F LONG_FIL.TXT;1 long_file_name.txt\n"
+  );
+}
+
+#[test]
+fn files_can_be_written_with_a_version() {
+  use isofs::identifier::IdentifierParts;
+
+  let dir = tempfile::tempdir().unwrap();
+  let source = dir.path().join("notes.txt");
+  std::fs::write(&source, b"third revision").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem
+    .upsert_file_versioned("notes.txt", &source, 3)
+    .unwrap();
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let identifier = std::str::from_utf8(&root[2].identifier).unwrap();
+  let parts = IdentifierParts::parse(identifier);
+
+  assert_eq!(identifier, "NOTES.TXT;3");
+  assert_eq!(parts.version, Some(3));
+  assert_eq!(parts.name, "NOTES");
+  assert_eq!(parts.extension, Some("TXT"));
+}
+
+#[test]
+fn file_versions_outside_the_valid_range_are_rejected() {
+  let dir = tempfile::tempdir().unwrap();
+  let source = dir.path().join("notes.txt");
+  std::fs::write(&source, b"").unwrap();
+
+  let mut filesystem = Filesystem::default();
+
+  for version in [0, 32768] {
+    assert!(matches!(
+      filesystem.upsert_file_versioned("notes.txt", &source, version),
+      Err(Error::InvalidFileVersion(v)) if v == version
+    ));
+  }
+}
+
+#[test]
+fn add_tree_with_policy_resolves_clashes() {
+  use isofs::writer::fs::MergePolicy;
+
+  let dir = tempfile::tempdir().unwrap();
+  let first = dir.path().join("first");
+  let second = dir.path().join("second");
+
+  for (tree, content) in [(&first, "first"), (&second, "second")] {
+    std::fs::create_dir_all(tree.join("a")).unwrap();
+    std::fs::write(tree.join("a/b.txt"), content).unwrap();
+  }
+
+  let merged_content = |policy| {
+    let mut filesystem = Filesystem::default();
+    filesystem
+      .add_tree_with_policy(&first, "", MergePolicy::Error)
+      .unwrap();
+    filesystem.add_tree_with_policy(&second, "", policy)?;
+
+    let iso = write_iso(filesystem);
+    let (root_lba, root_length) = root_extent(&iso);
+    let root = directory_records(&iso, root_lba, root_length);
+    let a = directory_records(&iso, root[2].extent_location, root[2].data_length);
+
+    assert_eq!(a.len(), 3);
+    assert_eq!(a[2].identifier, b"B.TXT;1");
+
+    Ok::<_, Error>(extent_bytes(&iso, &a[2]).to_vec())
+  };
+
+  assert_eq!(merged_content(MergePolicy::Overwrite).unwrap(), b"second");
+  assert_eq!(merged_content(MergePolicy::Skip).unwrap(), b"first");
+  assert!(matches!(
+    merged_content(MergePolicy::Error),
+    Err(Error::FileExists(path)) if path == "a/b.txt"
+  ));
+}
+
+#[test]
+fn deep_directories_are_relocated_into_rr_moved() {
+  fn max_depth(iso: &[u8], record: &Record, depth: usize) -> usize {
+    directory_records(iso, record.extent_location, record.data_length)
+      .iter()
+      .skip(2)
+      .filter(|r| r.file_flags & 0x02 != 0)
+      .map(|r| max_depth(iso, r, depth + 1))
+      .max()
+      .unwrap_or(depth)
+  }
+
+  fn lookup(iso: &[u8], records: Vec<Record>, path: &[&str]) -> Record {
+    let (first, rest) = path.split_first().unwrap();
+    let record = records
+      .into_iter()
+      .find(|r| r.identifier == first.as_bytes())
+      .unwrap();
+
+    match rest {
+      [] => record,
+      _ => lookup(
+        iso,
+        directory_records(iso, record.extent_location, record.data_length),
+        rest,
+      ),
+    }
+  }
+
+  let names = (1..=10).map(|ix| format!("d{ix}")).collect::<Vec<_>>();
+  let mut filesystem = Filesystem::default();
+  reader_file(
+    &mut filesystem,
+    &format!("{}/leaf.txt", names.join("/")),
+    b"deep",
+  );
+
+  let iso = write_iso_with(
+    WriterOptions {
+      deep_relocation: true,
+      ..Default::default()
+    },
+    filesystem,
+  );
+
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = Record {
+    identifier: vec![0],
+    extent_location: root_lba,
+    data_length: root_length,
+    file_flags: 0x02,
+  };
+  let records = || directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(max_depth(&iso, &root, 1), 8);
+
+  let d7 = lookup(&iso, records(), &["D1", "D2", "D3", "D4", "D5", "D6", "D7"]);
+  assert_eq!(
+    directory_records(&iso, d7.extent_location, d7.data_length).len(),
+    2
+  );
+
+  let leaf = lookup(
+    &iso,
+    records(),
+    &["RR_MOVED", "D8", "D9", "D10", "LEAF.TXT;1"],
+  );
+  assert_eq!(extent_bytes(&iso, &leaf), b"deep");
+}
+
+#[test]
+fn directory_records_shorter_than_their_fields_are_rejected() {
+  let record = record_with_flags(spec::FileFlags::empty());
+  let mut bytes = vec![0; record.extent()];
+  record.serialize(&mut bytes).unwrap();
+  bytes[0] -= 1;
+
+  assert!(matches!(
+    spec::DirectoryRecord::<spec::NoExtension>::parse(&bytes),
+    Err(isofs::parse::IsoParseError::RecordLengthMismatch {
+      declared: 43,
+      minimum: 44,
+      ..
+    })
+  ));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "DirectoryRecord length disagrees with its serialized size")]
+fn serializing_a_directory_record_with_a_stale_length_panics() {
+  let mut record = record_with_flags(spec::FileFlags::empty());
+  record.length -= 1;
+
+  let mut bytes = vec![0; record.extent()];
+  let _ = record.serialize(&mut bytes);
+}
+
+#[test]
+fn omit_version_writes_identifiers_without_a_suffix() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "hello.txt", b"hello");
+  reader_file(&mut filesystem, "dir/README", b"readme");
+
+  let iso = write_iso_with(
+    WriterOptions {
+      omit_version: true,
+      ..Default::default()
+    },
+    filesystem,
+  );
+
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let dir = directory_records(&iso, root[2].extent_location, root[2].data_length);
+
+  assert_eq!(root[3].identifier, b"HELLO.TXT");
+  assert_eq!(extent_bytes(&iso, &root[3]), b"hello");
+  assert_eq!(dir[2].identifier, b"README.");
+  assert!(!root[3].identifier.contains(&b';'));
+  assert!(!dir[2].identifier.contains(&b';'));
+}
+
+#[test]
+fn pvd_path_table_locations_are_single_endian() {
+  let bytes = serialize_pvd(&PrimaryVolume::builder("ENDIAN").build());
+  let mut pvd = spec::PrimaryVolumeDescriptor::parse(&bytes).unwrap();
+  pvd.type_l_path_table_location = 0x0102_0304;
+  pvd.optional_type_l_path_table_location = 0x0506_0708;
+  pvd.type_m_path_table_location = 0x1112_1314;
+  pvd.optional_type_m_path_table_location = 0x1516_1718;
+
+  let mut bytes = [0; 2048];
+  pvd.serialize(&mut bytes).unwrap();
+
+  assert_eq!(bytes[140..144], 0x0102_0304u32.to_le_bytes());
+  assert_eq!(bytes[144..148], 0x0506_0708u32.to_le_bytes());
+  assert_eq!(bytes[148..152], 0x1112_1314u32.to_be_bytes());
+  assert_eq!(bytes[152..156], 0x1516_1718u32.to_be_bytes());
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&bytes).unwrap();
+  assert_eq!(parsed.type_m_path_table_location, 0x1112_1314);
+  assert_eq!(parsed.optional_type_m_path_table_location, 0x1516_1718);
+}
+
+#[test]
+fn empty_files_share_a_sector_that_aliases_no_other_extent() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "empty1.txt", b"");
+  reader_file(&mut filesystem, "empty2.txt", b"");
+  reader_file(&mut filesystem, "full.txt", b"content");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let (empty1, empty2, full) = (&root[2], &root[3], &root[4]);
+
+  assert_eq!(empty1.data_length, 0);
+  assert_eq!(empty2.data_length, 0);
+  assert_eq!(empty1.extent_location, empty2.extent_location);
+  assert!(empty1.extent_location >= 18);
+  assert_ne!(empty1.extent_location, root_lba);
+  assert_ne!(empty1.extent_location, full.extent_location);
+  assert_eq!(extent_bytes(&iso, full), b"content");
+}
+
+#[test]
+fn lba_allocator_hands_out_one_empty_sector() {
+  let mut allocator = LbaAllocator::new(2048, 18);
+
+  assert_eq!(allocator.allocate_empty(), 18);
+  assert_eq!(allocator.allocate_sectors(1), 19);
+  assert_eq!(allocator.allocate_empty(), 18);
+}
+
+#[test]
+fn filesystem_iter_yields_full_paths_depth_first() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "a/b/c.txt", b"c");
+  reader_file(&mut filesystem, "a/d.txt", b"d");
+
+  let paths = filesystem
+    .iter()
+    .map(|(path, entry)| (path, entry.metadata().is_dir))
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    paths,
+    [
+      ("a".into(), true),
+      ("a/b".into(), true),
+      ("a/b/c.txt".into(), false),
+      ("a/d.txt".into(), false),
+    ]
+  );
+}
+
+#[test]
+fn pvd_file_identifiers_are_37_bytes_and_do_not_shift_dates() {
+  let bytes = serialize_pvd(&PrimaryVolume::builder("FIELDS").build());
+  let mut pvd = spec::PrimaryVolumeDescriptor::parse(&bytes).unwrap();
+  pvd.copyright_file_identifier = spec::DCharacters::from_bytes_truncated(&[b'C'; 37]);
+  pvd.abstract_file_identifier = spec::DCharacters::from_bytes_truncated(&[b'A'; 37]);
+  pvd.bibliographic_file_identifier = spec::DCharacters::from_bytes_truncated(&[b'B'; 37]);
+
+  let mut bytes = [0; 2048];
+  pvd.serialize(&mut bytes).unwrap();
+
+  let mut creation_date = [0; 17];
+  pvd.creation_date.serialize(&mut creation_date).unwrap();
+
+  assert!(bytes[702..739].iter().all(|&b| b == b'C'));
+  assert!(bytes[739..776].iter().all(|&b| b == b'A'));
+  assert!(bytes[776..813].iter().all(|&b| b == b'B'));
+  assert_eq!(bytes[813..830], creation_date);
+
+  let parsed = spec::PrimaryVolumeDescriptor::parse(&bytes).unwrap();
+  assert_eq!(
+    parsed.copyright_file_identifier.as_trimmed_str(),
+    "C".repeat(37)
+  );
+  assert_eq!(
+    parsed.abstract_file_identifier.as_trimmed_str(),
+    "A".repeat(37)
+  );
+  assert_eq!(
+    parsed.bibliographic_file_identifier.as_trimmed_str(),
+    "B".repeat(37)
+  );
+}
+
+#[test]
+fn directories_spanning_sectors_have_packed_data_length() {
+  let mut filesystem = Filesystem::default();
+
+  for ix in 0..100 {
+    reader_file(&mut filesystem, &format!("big/file{ix:03}.txt"), b"");
+  }
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let big = &root[2];
+
+  assert_eq!(root_length, SECTOR_SIZE as u32);
+  assert!(big.data_length >= 2 * SECTOR_SIZE as u32);
+  assert_eq!(big.data_length % SECTOR_SIZE as u32, 0);
+
+  let records = directory_records(&iso, big.extent_location, big.data_length);
+  let names = records[2..]
+    .iter()
+    .map(|r| r.identifier.clone())
+    .collect::<Vec<_>>();
+
+  assert_eq!(records[0].data_length, big.data_length);
+  assert_eq!(names.len(), 100);
+
+  for (ix, name) in names.iter().enumerate() {
+    assert_eq!(name, format!("FILE{ix:03}.TXT;1").as_bytes());
+  }
+}
+
+#[derive(Debug)]
+struct UpperIdentifier(String);
+
+impl IsoSerialize for UpperIdentifier {
+  fn extent(&self) -> usize {
+    self.0.len()
+  }
+
+  unsafe fn serialize_unchecked(
+    &self,
+    out: &mut [u8],
+  ) -> Result<(), isofs::serialize::IsoSerializeError> {
+    out[..self.0.len()].copy_from_slice(self.0.to_ascii_uppercase().as_bytes());
+    Ok(())
+  }
+}
+
+#[derive(Debug)]
+struct UpperExtension;
+
+impl spec::Extension for UpperExtension {
+  type FileIdentifier = UpperIdentifier;
+  type DirectoryIdentifier = UpperIdentifier;
+}
+
+#[test]
+fn directory_records_serialize_with_a_custom_extension() {
+  let record = spec::DirectoryRecord::<UpperExtension> {
+    length: 0,
+    extended_attribute_length: 0,
+    extent_location: 20,
+    data_length: 5,
+    recording_date: chrono::Utc::now().into(),
+    file_flags: spec::FileFlags::empty(),
+    file_unit_size: 0,
+    interleave_gap_size: 0,
+    volume_sequence_number: 1,
+    file_identifier_length: 0,
+    file_identifier: UpperIdentifier(String::new()),
+    system_use: vec![],
+  }
+  .with_identifier(UpperIdentifier("hello.txt;1".to_string()));
+
+  let mut bytes = vec![0; record.extent()];
+  record.serialize(&mut bytes).unwrap();
+
+  assert_eq!(bytes.len(), 44);
+  assert_eq!(bytes[0], 44);
+  assert_eq!(bytes[32], 11);
+  assert_eq!(&bytes[33..44], b"HELLO.TXT;1");
+  assert_eq!(u32_le(&bytes[2..]), 20);
+}
+
+/// Records identifiers lowercased and without their version.
+#[derive(Debug)]
+struct LowerExtension;
+
+impl spec::Extension for LowerExtension {
+  type FileIdentifier = spec::FileIdentifier<37>;
+  type DirectoryIdentifier = spec::DirectoryIdentifier<31>;
+}
+
+impl isofs::writer::WriterExtension for LowerExtension {
+  fn file_identifier(identifier: &str) -> Self::FileIdentifier {
+    let identifier = identifier.split(';').next().unwrap().to_ascii_lowercase();
+    spec::FileIdentifier::from_bytes_truncated(identifier.as_bytes())
+  }
+
+  fn current_directory() -> Self::FileIdentifier {
+    spec::FileIdentifier::current_directory()
+  }
+
+  fn parent_directory() -> Self::FileIdentifier {
+    spec::FileIdentifier::parent_directory()
+  }
+}
+
+#[test]
+fn directory_trees_are_written_with_a_custom_extension() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "dir/file.txt", b"content");
+
+  let mut iso = IsoWriter::<LowerExtension>::with_extension(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+  let mut out = std::io::Cursor::new(Vec::new());
+  iso.write(&mut out).unwrap();
+  let iso = out.into_inner();
+
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  assert_eq!(root[2].identifier, b"dir");
+
+  let dir = directory_records(&iso, root[2].extent_location, root[2].data_length);
+  assert_eq!(dir[2].identifier, b"file.txt");
+  assert_eq!(extent_bytes(&iso, &dir[2]), b"content");
+}
+
+#[test]
+fn try_parse_sector_never_panics_on_random_input() {
+  use spec::VolumeDescriptor;
+
+  // xorshift64, seeded so failures reproduce.
+  let mut state = 0x2545_f491_4f6c_dd1du64;
+  let mut next = move || {
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    state
+  };
+
+  for ix in 0..2000 {
+    let mut sector = [0; 2048];
+    sector.iter_mut().for_each(|b| *b = next().to_le_bytes()[0]);
+
+    // Mostly plausible headers, so the descriptor bodies get exercised.
+    if ix % 4 != 0 {
+      sector[0] = [1, 2, 255][ix % 3];
+      sector[1..6].copy_from_slice(b"CD001");
+    }
+
+    let _ = VolumeDescriptor::try_parse_sector(&sector);
+  }
+
+  let pvd = serialize_pvd(&PrimaryVolume::builder("SECTOR").build());
+  assert!(matches!(
+    VolumeDescriptor::try_parse_sector(&pvd),
+    Ok(Some(VolumeDescriptor::Primary(_)))
+  ));
+  assert!(matches!(
+    VolumeDescriptor::try_parse_sector(&[0; 2048]),
+    Ok(None)
+  ));
+
+  let mut malformed = pvd;
+  malformed[813] = b'x';
+  assert!(VolumeDescriptor::try_parse_sector(&malformed).is_err());
+}
+
+#[test]
+fn directory_record_capacity_leaves_room_for_system_use() {
+  type Record = spec::DirectoryRecord<spec::NoExtension>;
+
+  // 33 fixed bytes, a 10 byte identifier and its padding byte.
+  assert_eq!(Record::capacity_for("FILE.TXT;1".len()), 211);
+  assert_eq!(Record::capacity_for(1), 221);
+
+  // A 200 byte Rock Ridge NM entry alone needs 205 bytes, so it cannot fit.
+  assert_eq!(Record::capacity_for(200), 21);
+  assert!(Record::capacity_for(200) < 5 + 200);
+
+  assert_eq!(Record::capacity_for(300), 0);
+}
+
+#[test]
+fn identifier_cmp_follows_ecma_119_ordering() {
+  use isofs::identifier_cmp;
+  use std::cmp::Ordering;
+
+  assert_eq!(identifier_cmp("FILE", "FILE.TXT"), Ordering::Less);
+  assert_eq!(identifier_cmp("FILE.;1", "FILE.TXT;1"), Ordering::Less);
+  assert_eq!(identifier_cmp("A", "AA"), Ordering::Less);
+  assert_eq!(identifier_cmp("A.TXT;1", "AA.TXT;1"), Ordering::Less);
+  assert_eq!(identifier_cmp("F.T;2", "F.T;1"), Ordering::Less);
+  assert_eq!(identifier_cmp("F.T;1", "F.T;2"), Ordering::Greater);
+  assert_eq!(identifier_cmp("F.T;1", "F.T;1"), Ordering::Equal);
+}
+
+#[test]
+fn directory_records_are_written_in_identifier_order() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "b.txt", b"b");
+  reader_file(&mut filesystem, "aa.txt", b"aa");
+  reader_file(&mut filesystem, "a", b"a");
+  reader_file(&mut filesystem, "a.txt", b"a.txt");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let identifiers = directory_records(&iso, root_lba, root_length)[2..]
+    .iter()
+    .map(|r| String::from_utf8(r.identifier.clone()).unwrap())
+    .collect::<Vec<_>>();
+
+  assert_eq!(identifiers, ["A.;1", "A.TXT;1", "AA.TXT;1", "B.TXT;1"]);
+}
+
+#[test]
+fn writing_twice_rereads_host_files_but_not_readers() {
+  let dir = tempfile::tempdir().unwrap();
+  let source = dir.path().join("hello.txt");
+  std::fs::write(&source, b"hello").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_file("hello.txt", &source).unwrap();
+
+  let mut iso = IsoWriter::new(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  iso.write(std::io::Cursor::new(Vec::new())).unwrap();
+  let mut second = std::io::Cursor::new(Vec::new());
+  iso.write(&mut second).unwrap();
+
+  let second = second.into_inner();
+  let (root_lba, root_length) = root_extent(&second);
+  let root = directory_records(&second, root_lba, root_length);
+
+  assert_eq!(extent_bytes(&second, &root[2]), b"hello");
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "stream.txt", b"streamed");
+
+  let mut iso = IsoWriter::new(WriterOptions::default());
+  iso.add_volume(
+    PrimaryVolume::builder("TEST")
+      .filesystem(filesystem)
+      .build(),
+  );
+
+  iso.write(std::io::Cursor::new(Vec::new())).unwrap();
+  assert!(matches!(
+    iso.write(std::io::Cursor::new(Vec::new())),
+    Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+  ));
+}
+
+#[test]
+fn long_names_are_truncated_without_breaking_record_lengths() {
+  use isofs::identifier::InterchangeLevel;
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, &format!("{}.txt", "a".repeat(40)), b"long");
+  reader_file(
+    &mut filesystem,
+    &format!("{}/inner.txt", "b".repeat(40)),
+    b"inner",
+  );
+
+  let iso = write_iso_with(
+    WriterOptions {
+      interchange_level: InterchangeLevel::Level2,
+      ..Default::default()
+    },
+    filesystem,
+  );
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(root.len(), 4);
+  assert_eq!(
+    root[2].identifier,
+    format!("{}.TXT;1", "A".repeat(27)).as_bytes()
+  );
+  assert_eq!(root[3].identifier, "B".repeat(31).as_bytes());
+
+  let dir = directory_records(&iso, root[3].extent_location, root[3].data_length);
+  assert_eq!(dir[2].identifier, b"INNER.TXT;1");
+}
+
+#[test]
+fn every_allocated_sector_belongs_to_an_extent() {
+  fn cover(iso: &[u8], record: &Record, covered: &mut std::collections::BTreeSet<u32>) {
+    let sectors = record.data_length.div_ceil(SECTOR_SIZE as u32).max(1);
+    covered.extend(record.extent_location..record.extent_location + sectors);
+
+    if record.file_flags & 0x02 != 0 {
+      for child in directory_records(iso, record.extent_location, record.data_length)
+        .iter()
+        .skip(2)
+      {
+        cover(iso, child, covered);
+      }
+    }
+  }
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "a/b/c.txt", b"c");
+  reader_file(&mut filesystem, "a/d.txt", b"d");
+  reader_file(&mut filesystem, "e/empty.txt", b"");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = Record {
+    identifier: vec![0],
+    extent_location: root_lba,
+    data_length: root_length,
+    file_flags: 0x02,
+  };
+
+  let mut covered = std::collections::BTreeSet::new();
+  cover(&iso, &root, &mut covered);
+
+  // Everything past the PVD and set terminator.
+  let expected = (18..(iso.len() / SECTOR_SIZE) as u32).collect::<std::collections::BTreeSet<_>>();
+  assert_eq!(covered, expected);
+}
+
+#[test]
+fn host_files_are_copied_up_to_their_staged_size() {
+  let dir = tempfile::tempdir().unwrap();
+  let first = dir.path().join("first");
+  let second = dir.path().join("second");
+  std::fs::write(&first, b"hello").unwrap();
+  std::fs::write(&second, b"world").unwrap();
+
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_file("A.TXT", &first).unwrap();
+  filesystem.upsert_file("B.TXT", &second).unwrap();
+
+  std::fs::write(&first, vec![b'x'; 3 * SECTOR_SIZE]).unwrap();
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(extent_bytes(&iso, &root[2]), b"xxxxx");
+  assert_eq!(extent_bytes(&iso, &root[3]), b"world");
+
+  let mut filesystem = Filesystem::default();
+  filesystem.upsert_file("A.TXT", &second).unwrap();
+  std::fs::write(&second, b"wor").unwrap();
+
+  assert!(matches!(
+    try_write_volume(
+      PrimaryVolume::builder("TEST")
+        .filesystem(filesystem)
+        .build()
+    ),
+    Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof
+  ));
+}
+
+#[test]
+fn names_truncated_to_the_same_identifier_are_told_apart() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "document1.txt", b"one");
+  reader_file(&mut filesystem, "document2.txt", b"two");
+  reader_file(&mut filesystem, "directory1/a.txt", b"a");
+  reader_file(&mut filesystem, "directory2/b.txt", b"b");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let identifiers = root[2..]
+    .iter()
+    .map(|r| String::from_utf8(r.identifier.clone()).unwrap())
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    identifiers,
+    ["DIRECTOR", "DIRECT_1", "DOCUMENT.TXT;1", "DOCUME_1.TXT;1"]
+  );
+  assert_eq!(extent_bytes(&iso, &root[4]), b"one");
+  assert_eq!(extent_bytes(&iso, &root[5]), b"two");
+}
+
+#[test]
+fn names_differing_only_in_case_are_told_apart() {
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "a.txt", b"lower");
+  reader_file(&mut filesystem, "A.TXT", b"upper");
+
+  let iso = write_iso(filesystem);
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+
+  assert_eq!(root.len(), 4);
+  assert_eq!(root[2].identifier, b"A.TXT;1");
+  assert_eq!(extent_bytes(&iso, &root[2]), b"lower");
+  assert_eq!(root[3].identifier, b"A_1.TXT;1");
+  assert_eq!(extent_bytes(&iso, &root[3]), b"upper");
+}
+
+#[test]
+fn relocated_directories_with_clashing_identifiers_stay_apart() {
+  // `longdirectory` sits at level 9, one below the deepest allowed.
+  let deep = |prefix: &str| {
+    (1..=7)
+      .map(|ix| format!("{prefix}{ix}"))
+      .collect::<Vec<_>>()
+      .join("/")
+  };
+
+  let mut filesystem = Filesystem::default();
+  reader_file(&mut filesystem, "RR_MOVED/longdirectory/z.txt", b"z");
+  reader_file(
+    &mut filesystem,
+    &format!("{}/longdirectory/x.txt", deep("a")),
+    b"x",
+  );
+  reader_file(
+    &mut filesystem,
+    &format!("{}/longdirectory/y.txt", deep("b")),
+    b"y",
+  );
+
+  let iso = write_iso_with(
+    WriterOptions {
+      deep_relocation: true,
+      ..Default::default()
+    },
+    filesystem,
+  );
+
+  let (root_lba, root_length) = root_extent(&iso);
+  let root = directory_records(&iso, root_lba, root_length);
+  let rr_moved = root.iter().find(|r| r.identifier == b"RR_MOVED").unwrap();
+  let moved = directory_records(&iso, rr_moved.extent_location, rr_moved.data_length);
+
+  let contents = moved[2..]
+    .iter()
+    .map(|dir| {
+      let files = directory_records(&iso, dir.extent_location, dir.data_length);
+      (
+        String::from_utf8(dir.identifier.clone()).unwrap(),
+        files[2..]
+          .iter()
+          .map(|f| String::from_utf8(f.identifier.clone()).unwrap())
+          .collect::<Vec<_>>(),
+      )
+    })
+    .collect::<Vec<_>>();
+
+  assert_eq!(
+    contents,
+    [
+      ("LONGDIRE".to_string(), vec!["Z.TXT;1".to_string()]),
+      ("LONGDI_1".to_string(), vec!["X.TXT;1".to_string()]),
+      ("LONGDI_2".to_string(), vec!["Y.TXT;1".to_string()]),
+    ]
+  );
+}